@@ -0,0 +1,67 @@
+//! Steps the `www/index.js` demo board with each way of stepping the
+//! default rule. Run with `cargo bench --bench step`.
+
+use std::time::{Duration, Instant};
+
+use wasm_game_of_life::{Backend, Grid};
+
+const GENERATIONS: usize = 500;
+
+type Setup = (&'static str, fn(&mut Grid));
+
+fn demo_grid() -> Grid {
+    let mut grid = Grid::new(200, 200);
+    grid.spawn_glider_gun(75, 15);
+    grid.spawn_pulsar(10, 35);
+    grid.spawn_pentadecanthlon(25, 55);
+    grid.spawn_acorn(25, 175);
+    grid.spawn_r_pentomino(180, 25);
+    grid.spawn_glider_loop(100, 100);
+    grid.spawn_pulsar(100, 100);
+    grid.spawn_infinite_growth_1(175, 175);
+    grid
+}
+
+fn run(setup: fn(&mut Grid), warm_up: usize) -> (Duration, usize) {
+    let mut grid = demo_grid();
+    setup(&mut grid);
+    for _ in 0..warm_up {
+        grid.step_forward();
+    }
+    let start = Instant::now();
+    for _ in 0..GENERATIONS {
+        grid.step_forward();
+    }
+    (start.elapsed(), grid.population())
+}
+
+fn main() {
+    let setups: [Setup; 3] = [
+        ("full", |grid| grid.set_incremental(false)),
+        ("incremental", |_| {}),
+        ("bit-parallel", |grid| {
+            grid.set_backend(Backend::BitParallel)
+        }),
+    ];
+
+    // The demo board fills up for the first ~250 generations and then
+    // settles into still lifes and oscillators.
+    for warm_up in [0, 300] {
+        println!(
+            "{} generations on the 200x200 demo board after {}",
+            GENERATIONS, warm_up
+        );
+        let mut baseline = None;
+        for (name, setup) in setups.iter() {
+            let (elapsed, population) = run(*setup, warm_up);
+            let (full, full_population) = *baseline.get_or_insert((elapsed, population));
+            assert_eq!(population, full_population);
+            println!(
+                "{:<13} {:>12?} {:.1}x",
+                name,
+                elapsed,
+                full.as_secs_f64() / elapsed.as_secs_f64()
+            );
+        }
+    }
+}
@@ -0,0 +1,271 @@
+use std::convert::TryFrom;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::draw;
+use crate::image::ImageError;
+use crate::utils;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+}
+
+/// Settings for `Grid::record_animation`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Animation {
+    format: AnimationFormat,
+    cell_px: usize,
+    delay_ms: u32,
+    palette: Vec<[u8; 3]>,
+    region: Option<(usize, usize, usize, usize)>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Animation {
+    pub fn new(format: AnimationFormat) -> Animation {
+        Animation {
+            format,
+            cell_px: 4,
+            delay_ms: 100,
+            palette: utils::DEFAULT_COLOURS.to_vec(),
+            region: None,
+        }
+    }
+
+    pub fn format(&self) -> AnimationFormat {
+        self.format
+    }
+
+    pub fn set_cell_px(&mut self, cell_px: usize) {
+        self.cell_px = cell_px.max(1);
+    }
+
+    /// Time each generation is shown for. GIF rounds it to 10ms.
+    pub fn set_delay_ms(&mut self, delay_ms: u32) {
+        self.delay_ms = delay_ms;
+    }
+
+    /// Flat RGB triples for state 0 (dead) and then each live colour, as in
+    /// `Grid::to_png`.
+    pub fn set_colours(&mut self, colours: &[u8]) {
+        self.palette = utils::palette(colours);
+        self.palette.truncate(256);
+    }
+
+    /// Only records the `width` x `height` cells from (`x`, `y`).
+    pub fn set_region(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        self.region = Some((x, y, width, height));
+    }
+
+    pub fn clear_region(&mut self) {
+        self.region = None;
+    }
+}
+
+/// Cells that changed since the previous frame, and how long they are shown.
+#[derive(Debug)]
+struct Patch {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    delay_ms: u32,
+}
+
+impl Animation {
+    /// The recorded area of a `width` x `height` grid, clamped to the grid.
+    pub(crate) fn region(&self, width: usize, height: usize) -> (usize, usize, usize, usize) {
+        draw::clip_region(self.region.unwrap_or((0, 0, width, height)), width, height)
+    }
+
+    /// Encodes frames of `width` x `height` row-major cell states. Each frame
+    /// only stores the rectangle that changed; unchanged generations extend
+    /// the previous frame instead.
+    pub(crate) fn encode(
+        &self,
+        width: usize,
+        height: usize,
+        frames: &[Vec<u8>],
+    ) -> Result<Vec<u8>, ImageError> {
+        if width == 0 || height == 0 || frames.is_empty() {
+            return Err(ImageError::Encode("nothing to record".to_string()));
+        }
+
+        let mut patches = vec![Patch {
+            x: 0,
+            y: 0,
+            width,
+            height,
+            delay_ms: self.delay_ms,
+        }];
+        let mut shown = vec![0];
+        for (i, pair) in frames.windows(2).enumerate() {
+            match changed_bounds(width, &pair[0], &pair[1]) {
+                Some((x, y, w, h)) => {
+                    patches.push(Patch {
+                        x,
+                        y,
+                        width: w,
+                        height: h,
+                        delay_ms: self.delay_ms,
+                    });
+                    shown.push(i + 1);
+                }
+                None => {
+                    let last = patches.len() - 1;
+                    patches[last].delay_ms += self.delay_ms;
+                }
+            }
+        }
+
+        let pixels: Vec<_> = patches
+            .iter()
+            .zip(&shown)
+            .map(|(patch, &frame)| self.render(width, &frames[frame], patch))
+            .collect();
+        match self.format {
+            AnimationFormat::Gif => self.encode_gif(width, height, &patches, &pixels),
+            AnimationFormat::Apng => self.encode_apng(width, height, &patches, &pixels),
+        }
+    }
+
+    // Palette indices of a patch, `cell_px` pixels per cell.
+    fn render(&self, width: usize, states: &[u8], patch: &Patch) -> Vec<u8> {
+        let row_px = patch.width * self.cell_px;
+        let mut pixels = Vec::with_capacity(row_px * patch.height * self.cell_px);
+        for y in patch.y..patch.y + patch.height {
+            let row: Vec<u8> = states[y * width + patch.x..y * width + patch.x + patch.width]
+                .iter()
+                .flat_map(|state| {
+                    std::iter::repeat_n((*state as usize % self.palette.len()) as u8, self.cell_px)
+                })
+                .collect();
+            for _ in 0..self.cell_px {
+                pixels.extend_from_slice(&row);
+            }
+        }
+        pixels
+    }
+
+    fn flat_palette(&self) -> Vec<u8> {
+        self.palette.iter().flatten().copied().collect()
+    }
+
+    fn encode_gif(
+        &self,
+        width: usize,
+        height: usize,
+        patches: &[Patch],
+        pixels: &[Vec<u8>],
+    ) -> Result<Vec<u8>, ImageError> {
+        let error = |e: gif::EncodingError| ImageError::Encode(e.to_string());
+        let px = |cells: usize| {
+            u16::try_from(cells * self.cell_px)
+                .map_err(|_| ImageError::Encode("too large for a GIF".to_string()))
+        };
+
+        let mut bytes = vec![];
+        {
+            let palette = self.flat_palette();
+            let mut encoder =
+                gif::Encoder::new(&mut bytes, px(width)?, px(height)?, &palette).map_err(error)?;
+            encoder.set_repeat(gif::Repeat::Infinite).map_err(error)?;
+            for (patch, pixels) in patches.iter().zip(pixels) {
+                let frame = gif::Frame {
+                    delay: u16::try_from((patch.delay_ms + 5) / 10).unwrap_or(u16::MAX),
+                    left: px(patch.x)?,
+                    top: px(patch.y)?,
+                    width: px(patch.width)?,
+                    height: px(patch.height)?,
+                    buffer: pixels.into(),
+                    ..gif::Frame::default()
+                };
+                encoder.write_frame(&frame).map_err(error)?;
+            }
+        }
+        Ok(bytes)
+    }
+
+    fn encode_apng(
+        &self,
+        width: usize,
+        height: usize,
+        patches: &[Patch],
+        pixels: &[Vec<u8>],
+    ) -> Result<Vec<u8>, ImageError> {
+        let error = |e: png::EncodingError| ImageError::Encode(e.to_string());
+        let px = |cells: usize| (cells * self.cell_px) as u32;
+
+        let mut bytes = vec![];
+        let mut encoder = png::Encoder::new(&mut bytes, px(width), px(height));
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(self.flat_palette());
+        encoder
+            .set_animated(patches.len() as u32, 0)
+            .map_err(error)?;
+        let mut writer = encoder.write_header().map_err(error)?;
+        for (patch, pixels) in patches.iter().zip(pixels) {
+            // The position is checked against the current size and the size
+            // against the current position, so start from the origin.
+            writer.reset_frame_position().map_err(error)?;
+            writer
+                .set_frame_dimension(px(patch.width), px(patch.height))
+                .map_err(error)?;
+            writer
+                .set_frame_position(px(patch.x), px(patch.y))
+                .map_err(error)?;
+            let delay = u16::try_from(patch.delay_ms).unwrap_or(u16::MAX);
+            writer.set_frame_delay(delay, 1000).map_err(error)?;
+            writer.set_blend_op(png::BlendOp::Source).map_err(error)?;
+            writer.set_dispose_op(png::DisposeOp::None).map_err(error)?;
+            writer.write_image_data(pixels).map_err(error)?;
+        }
+        writer.finish().map_err(error)?;
+        Ok(bytes)
+    }
+}
+
+// Bounding box of the cells that differ between two frames.
+fn changed_bounds(
+    width: usize,
+    before: &[u8],
+    after: &[u8],
+) -> Option<(usize, usize, usize, usize)> {
+    let mut bounds: Option<(usize, usize, usize, usize)> = None;
+    for (i, _) in before
+        .iter()
+        .zip(after)
+        .enumerate()
+        .filter(|(_, (a, b))| a != b)
+    {
+        let (x, y) = (i % width, i / width);
+        bounds = Some(match bounds {
+            Some((left, top, right, bottom)) => {
+                (left.min(x), top.min(y), right.max(x), bottom.max(y))
+            }
+            None => (x, y, x, y),
+        });
+    }
+    bounds.map(|(left, top, right, bottom)| (left, top, right - left + 1, bottom - top + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_bounds_test() {
+        let before = [0, 0, 0, 0, 0, 0];
+        assert_eq!(changed_bounds(3, &before, &before), None);
+        assert_eq!(
+            changed_bounds(3, &before, &[0, 1, 0, 0, 0, 2]),
+            Some((1, 0, 2, 2))
+        );
+    }
+}
@@ -0,0 +1,13 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Backend {
+    /// Every cell looks at its neighbours on its own.
+    Cells,
+    /// Cells are packed 64 to a word and counted with bitwise adders. Only
+    /// used for B/S rules with synchronous, noise free stepping.
+    BitParallel,
+}
@@ -0,0 +1,320 @@
+//! Terminal viewer for watching and editing a grid, e.g. over SSH.
+//!
+//! ```text
+//! gol-tui [--size 200x200] [pattern.rle]
+//! ```
+//!
+//! Keys: arrows/hjkl move the cursor, wasd pan, z zoom, space pause, n step,
+//! +/- speed, enter toggle a cell, p/P pick a pattern, o spawn it, r random,
+//! c clear, q quit.
+
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::{cursor, execute, queue, terminal};
+use wasm_game_of_life::{parse_plaintext, CellState, Grid, Pattern};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Zoom {
+    /// 2x4 cells per character.
+    Braille,
+    /// 1x2 cells per character.
+    HalfBlock,
+    /// One cell per two characters.
+    Full,
+}
+
+impl Zoom {
+    fn next(self) -> Self {
+        match self {
+            Zoom::Braille => Zoom::HalfBlock,
+            Zoom::HalfBlock => Zoom::Full,
+            Zoom::Full => Zoom::Braille,
+        }
+    }
+
+    // Cells covered by one character (or character pair at `Full`).
+    fn cells_per_char(self) -> (i64, i64) {
+        match self {
+            Zoom::Braille => (2, 4),
+            Zoom::HalfBlock => (1, 2),
+            Zoom::Full => (1, 1),
+        }
+    }
+
+    fn chars_per_cell(self) -> u16 {
+        match self {
+            Zoom::Full => 2,
+            _ => 1,
+        }
+    }
+}
+
+// Braille dot bits for the cells of a 2x4 block, indexed [y][x].
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+fn glyph(zoom: Zoom, alive: impl Fn(i64, i64) -> bool) -> String {
+    match zoom {
+        Zoom::Braille => {
+            let mut bits = 0;
+            for (dy, row) in BRAILLE_DOTS.iter().enumerate() {
+                for (dx, bit) in row.iter().enumerate() {
+                    if alive(dx as i64, dy as i64) {
+                        bits |= bit;
+                    }
+                }
+            }
+            std::char::from_u32(0x2800 + bits)
+                .unwrap_or(' ')
+                .to_string()
+        }
+        Zoom::HalfBlock => match (alive(0, 0), alive(0, 1)) {
+            (true, true) => "█",
+            (true, false) => "▀",
+            (false, true) => "▄",
+            (false, false) => " ",
+        }
+        .to_string(),
+        Zoom::Full => if alive(0, 0) { "██" } else { "  " }.to_string(),
+    }
+}
+
+struct Viewer {
+    grid: Grid,
+    view: (i64, i64),
+    cursor: (i64, i64),
+    zoom: Zoom,
+    paused: bool,
+    delay: Duration,
+    patterns: Vec<Pattern>,
+    pattern: usize,
+}
+
+impl Viewer {
+    fn is_alive(&self, x: i64, y: i64) -> bool {
+        self.grid
+            .cell(x, y)
+            .is_some_and(|cell| cell.state == CellState::Alive)
+    }
+
+    // Grid area shown in a `columns` x `rows` character area.
+    fn visible(&self, columns: u16, rows: u16) -> (i64, i64) {
+        let (cx, cy) = self.zoom.cells_per_char();
+        let columns = (columns / self.zoom.chars_per_cell()) as i64;
+        (columns * cx, rows as i64 * cy)
+    }
+
+    // Pans the view so the cursor stays on screen.
+    fn follow_cursor(&mut self, columns: u16, rows: u16) {
+        let (width, height) = self.visible(columns, rows);
+        if self.cursor.0 < self.view.0 {
+            self.view.0 = self.cursor.0;
+        } else if self.cursor.0 >= self.view.0 + width {
+            self.view.0 = self.cursor.0 - width + 1;
+        }
+        if self.cursor.1 < self.view.1 {
+            self.view.1 = self.cursor.1;
+        } else if self.cursor.1 >= self.view.1 + height {
+            self.view.1 = self.cursor.1 - height + 1;
+        }
+    }
+
+    fn draw(&self, out: &mut impl Write, columns: u16, rows: u16) -> Result<()> {
+        let (cx, cy) = self.zoom.cells_per_char();
+        let grid_rows = rows.saturating_sub(1);
+        let chars = columns / self.zoom.chars_per_cell();
+        for row in 0..grid_rows {
+            queue!(out, cursor::MoveTo(0, row))?;
+            for column in 0..chars {
+                let left = self.view.0 + column as i64 * cx;
+                let top = self.view.1 + row as i64 * cy;
+                let text = glyph(self.zoom, |dx, dy| self.is_alive(left + dx, top + dy));
+                let under_cursor = (left..left + cx).contains(&self.cursor.0)
+                    && (top..top + cy).contains(&self.cursor.1);
+                if under_cursor {
+                    queue!(
+                        out,
+                        SetAttribute(Attribute::Reverse),
+                        Print(text),
+                        SetAttribute(Attribute::NoReverse)
+                    )?;
+                } else {
+                    queue!(out, Print(text))?;
+                }
+            }
+        }
+
+        let status = format!(
+            "gen {} | pop {} | {} | {}ms | {:?} | ({}, {}) | pattern: {}",
+            self.grid.generation(),
+            self.grid.population(),
+            if self.paused { "paused" } else { "running" },
+            self.delay.as_millis(),
+            self.zoom,
+            self.cursor.0,
+            self.cursor.1,
+            self.patterns[self.pattern],
+        );
+        let status: String = status.chars().take(columns as usize).collect();
+        queue!(
+            out,
+            cursor::MoveTo(0, grid_rows),
+            terminal::Clear(terminal::ClearType::CurrentLine),
+            Print(status)
+        )?;
+        out.flush()?;
+        Ok(())
+    }
+
+    // Returns false when the viewer should quit.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let pan = {
+            let (cx, cy) = self.zoom.cells_per_char();
+            (cx * 8, cy * 4)
+        };
+        let limit = (self.grid.width() as i64 - 1, self.grid.height() as i64 - 1);
+        let mut move_cursor = |dx: i64, dy: i64| {
+            self.cursor.0 = (self.cursor.0 + dx).clamp(0, limit.0.max(0));
+            self.cursor.1 = (self.cursor.1 + dy).clamp(0, limit.1.max(0));
+        };
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Left | KeyCode::Char('h') => move_cursor(-1, 0),
+            KeyCode::Right | KeyCode::Char('l') => move_cursor(1, 0),
+            KeyCode::Up | KeyCode::Char('k') => move_cursor(0, -1),
+            KeyCode::Down | KeyCode::Char('j') => move_cursor(0, 1),
+            KeyCode::Char('a') => move_cursor(-pan.0, 0),
+            KeyCode::Char('d') => move_cursor(pan.0, 0),
+            KeyCode::Char('w') => move_cursor(0, -pan.1),
+            KeyCode::Char('s') => move_cursor(0, pan.1),
+            KeyCode::Char('z') => self.zoom = self.zoom.next(),
+            KeyCode::Char(' ') => self.paused = !self.paused,
+            KeyCode::Char('n') => self.grid.step_forward(),
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.delay = (self.delay / 2).max(Duration::from_millis(1))
+            }
+            KeyCode::Char('-') => self.delay = (self.delay * 2).min(Duration::from_secs(2)),
+            KeyCode::Enter | KeyCode::Char('x') => {
+                let (x, y) = self.cursor;
+                let _ = if self.is_alive(x, y) {
+                    self.grid.set_dead(x, y)
+                } else {
+                    self.grid.set_alive(x, y, 0)
+                };
+            }
+            KeyCode::Char('p') => self.pattern = (self.pattern + 1) % self.patterns.len(),
+            KeyCode::Char('P') => {
+                self.pattern = (self.pattern + self.patterns.len() - 1) % self.patterns.len()
+            }
+            KeyCode::Char('o') => {
+                let (x, y) = self.cursor;
+                self.grid
+                    .spawn_pattern(self.patterns[self.pattern], x as usize, y as usize, 0);
+            }
+            KeyCode::Char('r') => self.grid.randomize(),
+            KeyCode::Char('c') => self.grid.reset(),
+            _ => {}
+        }
+        true
+    }
+
+    fn run(&mut self, out: &mut impl Write) -> Result<()> {
+        let mut last_step = Instant::now();
+        loop {
+            let (columns, rows) = terminal::size()?;
+            self.follow_cursor(columns, rows.saturating_sub(1));
+            self.draw(out, columns, rows)?;
+
+            let timeout = self.delay.saturating_sub(last_step.elapsed());
+            if event::poll(timeout)? {
+                match event::read()? {
+                    Event::Key(key)
+                        if key.kind != KeyEventKind::Release && !self.handle_key(key) =>
+                    {
+                        return Ok(());
+                    }
+                    Event::Resize(..) => queue!(out, terminal::Clear(terminal::ClearType::All))?,
+                    _ => {}
+                }
+            }
+            if !self.paused && last_step.elapsed() >= self.delay {
+                self.grid.step_forward();
+                last_step = Instant::now();
+            }
+        }
+    }
+}
+
+fn load(args: &[String]) -> Result<Grid> {
+    let mut size = (200, 200);
+    let mut pattern = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--size" {
+            let value = args.next().ok_or("--size needs a value like 200x200")?;
+            let (width, height) = value.split_once('x').ok_or("size must look like 200x200")?;
+            size = (width.parse()?, height.parse()?);
+        } else {
+            pattern = Some(arg);
+        }
+    }
+
+    let mut grid = Grid::new(size.0, size.1);
+    match pattern {
+        Some(path) => {
+            let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            if path.ends_with(".cells") {
+                for (x, y) in parse_plaintext(&text) {
+                    grid.set_alive(x as i64 + 10, y as i64 + 10, 0)?;
+                }
+            } else {
+                grid.rle_spawn(10, 10, &text, 0)?;
+            }
+        }
+        None => grid.randomize_center(),
+    }
+    Ok(grid)
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let grid = load(&args)?;
+    let mut viewer = Viewer {
+        cursor: (grid.width() as i64 / 2, grid.height() as i64 / 2),
+        grid,
+        view: (0, 0),
+        zoom: Zoom::Braille,
+        paused: false,
+        delay: Duration::from_millis(100),
+        patterns: Pattern::get_patterns(),
+        pattern: 0,
+    };
+
+    let mut out = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+    let result = viewer.run(&mut out);
+    execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glyph_test() {
+        assert_eq!(glyph(Zoom::Braille, |_, _| true), "⣿");
+        assert_eq!(glyph(Zoom::Braille, |x, y| (x, y) == (1, 3)), "⢀");
+        assert_eq!(glyph(Zoom::HalfBlock, |_, y| y == 0), "▀");
+        assert_eq!(glyph(Zoom::Full, |_, _| false), "  ");
+    }
+}
@@ -0,0 +1,455 @@
+//! Headless runs and pattern conversion on the same `Grid` as the web UI.
+//!
+//! ```text
+//! gol run [--rule B3/S23] [--gens 1000] [--margin 100] pattern.rle [-o out.rle]
+//! gol convert in.cells out.rle
+//! gol analyze [--rule B3/S23] [--gens 1000] pattern.rle
+//! gol render [--rule B3/S23] [--gen 500] [--cell 4] pattern.rle out.png
+//! gol render [--phases 3] [--labels 5] [--grid] pattern.rle out.svg
+//! gol record [--gens 300] [--cell 4] [--delay 100] pattern.rle out.gif
+//! ```
+//!
+//! Patterns are RLE, plaintext (`.cells`) or Macrocell (`.mc`), picked by
+//! extension. Without
+//! `--rule` the rule from the RLE header is used, then the grid's default.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use wasm_game_of_life::{
+    parse_plaintext, parse_rle, parse_rle_rule, write_plaintext, write_rle, Animation,
+    AnimationFormat, Grid, LifeRule, Macrocell, SvgStyle, MAX_CELLS,
+};
+
+const USAGE: &str = "usage:
+  gol run [--rule RULE] [--gens N] [--margin N] PATTERN [-o OUT]
+  gol convert IN OUT
+  gol analyze [--rule RULE] [--gens N] PATTERN
+  gol render [--rule RULE] [--gen N] [--cell PX] [--margin N] PATTERN OUT.png
+  gol render [--rule RULE] [--gen N] [--cell PX] [--margin N] [--phases N] [--labels N]
+             [--grid] PATTERN OUT.svg
+  gol record [--rule RULE] [--gens N] [--cell PX] [--delay MS] [--margin N] PATTERN OUT";
+
+// Options that take no value.
+const FLAGS: &[&str] = &["grid"];
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut positional = vec![];
+        let mut options = HashMap::new();
+        let mut flags = vec![];
+        while let Some(arg) = args.next() {
+            if let Some(name) = arg.strip_prefix("--").filter(|name| FLAGS.contains(name)) {
+                flags.push(name.to_string());
+            } else if let Some(name) = arg.strip_prefix("--").or_else(|| arg.strip_prefix('-')) {
+                let value = args.next().ok_or(format!("{} needs a value", arg))?;
+                options.insert(name.to_string(), value);
+            } else {
+                positional.push(arg);
+            }
+        }
+        Ok(Args {
+            positional,
+            options,
+            flags,
+        })
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+
+    fn number(&self, name: &str, default: usize) -> Result<usize> {
+        match self.options.get(name) {
+            Some(value) => Ok(value
+                .parse()
+                .map_err(|_| format!("--{} expects a number", name))?),
+            None => Ok(default),
+        }
+    }
+
+    fn input(&self, index: usize) -> Result<&str> {
+        self.positional
+            .get(index)
+            .map(String::as_str)
+            .ok_or_else(|| USAGE.into())
+    }
+}
+
+struct PatternFile {
+    cells: Vec<(usize, usize)>,
+    rule: Option<String>,
+}
+
+// Largest Macrocell pattern, in bounding box cells, expanded into a list.
+const MAX_EXPANDED_CELLS: u64 = 1 << 28;
+// Largest RLE pattern, in cells per side, for the same limit.
+const MAX_RLE_SIDE: usize = 1 << 14;
+
+fn has_extension(path: &str, name: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension == name)
+}
+
+fn is_plaintext(path: &str) -> bool {
+    has_extension(path, "cells")
+}
+
+fn read_macrocell(text: &str) -> Result<PatternFile> {
+    let pattern: Macrocell = text.parse()?;
+    let cells = match pattern.bounds() {
+        Some((left, top, width, height)) => {
+            if width.saturating_mul(height) > MAX_EXPANDED_CELLS {
+                return Err(format!("{}x{} is too large to expand", width, height).into());
+            }
+            pattern
+                .crop(left, top, width, height)
+                .into_iter()
+                .map(|(x, y, _)| ((x - left) as usize, (y - top) as usize))
+                .collect()
+        }
+        None => vec![],
+    };
+    Ok(PatternFile {
+        cells,
+        rule: pattern.rule().map(str::to_string),
+    })
+}
+
+fn read_pattern(path: &str) -> Result<PatternFile> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    if has_extension(path, "mc") {
+        return read_macrocell(&text);
+    }
+    Ok(if is_plaintext(path) {
+        PatternFile {
+            cells: parse_plaintext(&text),
+            rule: None,
+        }
+    } else {
+        PatternFile {
+            cells: parse_rle(&text, (MAX_RLE_SIDE, MAX_RLE_SIDE))?,
+            rule: parse_rle_rule(&text),
+        }
+    })
+}
+
+fn write_pattern(path: Option<&str>, cells: &[(usize, usize)], rule: &str) -> Result<()> {
+    match path {
+        Some(path) if is_plaintext(path) => {
+            let name = Path::new(path)
+                .file_stem()
+                .map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+            fs::write(path, write_plaintext(cells, &name))?
+        }
+        Some(path) if has_extension(path, "mc") => {
+            let cells: Vec<_> = cells
+                .iter()
+                .map(|(x, y)| (*x as i64, *y as i64, 1))
+                .collect();
+            let pattern = Macrocell::from_cells(&cells, Some(rule.to_string()), 0);
+            fs::write(path, pattern.to_string())?
+        }
+        Some(path) => fs::write(path, write_rle(cells, rule))?,
+        None => print!("{}", write_rle(cells, rule)),
+    }
+    Ok(())
+}
+
+// A grid with `margin` dead cells around the pattern on every side, up to
+// `MAX_CELLS` cells in all.
+fn load_grid(args: &Args, pattern: &PatternFile, margin: usize) -> Result<Grid> {
+    let width = pattern
+        .cells
+        .iter()
+        .map(|cell| cell.0 + 1)
+        .max()
+        .unwrap_or(0);
+    let height = pattern
+        .cells
+        .iter()
+        .map(|cell| cell.1 + 1)
+        .max()
+        .unwrap_or(0);
+    let side = |length: usize| margin.checked_mul(2).and_then(|m| m.checked_add(length));
+    let (width, height) = side(width)
+        .zip(side(height))
+        .filter(|(width, height)| width.checked_mul(*height).is_some_and(|c| c <= MAX_CELLS))
+        .ok_or_else(|| {
+            format!(
+                "a {}x{} pattern with a margin of {} is too large",
+                width, height, margin
+            )
+        })?;
+    let mut grid = Grid::new(width, height);
+    if let Some(rule) = args.options.get("rule").or(pattern.rule.as_ref()) {
+        grid.set_rule(rule)?;
+    }
+    for (x, y) in &pattern.cells {
+        grid.set_alive((x + margin) as i64, (y + margin) as i64, 0)?;
+    }
+    Ok(grid)
+}
+
+fn live_cells(grid: &Grid) -> Vec<(usize, usize)> {
+    grid.live().map(|(x, y)| (x as usize, y as usize)).collect()
+}
+
+fn run(args: &Args) -> Result<()> {
+    let pattern = read_pattern(args.input(0)?)?;
+    let mut grid = load_grid(args, &pattern, args.number("margin", 100)?)?;
+    for _ in 0..args.number("gens", 1000)? {
+        grid.step_forward();
+    }
+    write_pattern(
+        args.options.get("o").map(String::as_str),
+        &live_cells(&grid),
+        &grid.rule(),
+    )
+}
+
+fn convert(args: &Args) -> Result<()> {
+    let pattern = read_pattern(args.input(0)?)?;
+    let rule = args
+        .options
+        .get("rule")
+        .or(pattern.rule.as_ref())
+        .cloned()
+        .unwrap_or_else(|| LifeRule::default().to_string());
+    write_pattern(Some(args.input(1)?), &pattern.cells, &rule)
+}
+
+// Live cells shifted so their bounding box starts at the origin, together
+// with that origin.
+fn normalise(cells: &[(usize, usize)]) -> (Vec<(usize, usize)>, (i64, i64)) {
+    let left = cells.iter().map(|cell| cell.0).min().unwrap_or(0);
+    let top = cells.iter().map(|cell| cell.1).min().unwrap_or(0);
+    let shape = cells.iter().map(|(x, y)| (x - left, y - top)).collect();
+    (shape, (left as i64, top as i64))
+}
+
+// The first time a shape repeats: the generation its cycle starts, the
+// cycle's length, how far the shape moved over it, and the population of
+// every generation up to the repeat.
+struct Cycle {
+    start: usize,
+    period: usize,
+    displacement: (i64, i64),
+    populations: Vec<usize>,
+}
+
+impl Cycle {
+    // Steps `grid` up to `gens` generations looking for a repeat.
+    fn find(grid: &mut Grid, gens: usize) -> Option<Cycle> {
+        // Shape -> (generation, origin) of its first appearance.
+        let mut seen = HashMap::new();
+        let mut populations = vec![];
+        for generation in 0..=gens {
+            let cells = live_cells(grid);
+            populations.push(cells.len());
+            let (shape, origin) = normalise(&cells);
+            if let Some((start, previous)) = seen.insert(shape, (generation, origin)) {
+                return Some(Cycle {
+                    start,
+                    period: generation - start,
+                    displacement: (origin.0 - previous.0, origin.1 - previous.1),
+                    populations,
+                });
+            }
+            grid.step_forward();
+        }
+        None
+    }
+
+    fn kind(&self) -> &'static str {
+        if self.displacement != (0, 0) {
+            "spaceship"
+        } else if self.populations.last() == Some(&0) {
+            "dies out"
+        } else if self.period == 1 {
+            "still life"
+        } else {
+            "oscillator"
+        }
+    }
+
+    // Cells per generation as a reduced fraction of c, for spaceships.
+    fn speed(&self) -> Option<String> {
+        let (dx, dy) = self.displacement;
+        let cells = dx.unsigned_abs().max(dy.unsigned_abs()) as usize;
+        if cells == 0 {
+            return None;
+        }
+        let divisor = gcd(cells, self.period);
+        Some(match (cells / divisor, self.period / divisor) {
+            (1, 1) => "c".to_string(),
+            (1, period) => format!("c/{}", period),
+            (cells, period) => format!("{}c/{}", cells, period),
+        })
+    }
+}
+
+fn analyze(args: &Args) -> Result<()> {
+    let pattern = read_pattern(args.input(0)?)?;
+    let gens = args.number("gens", 1000)?;
+    let mut grid = load_grid(args, &pattern, gens / 2 + 2)?;
+    println!("population: {}", grid.population());
+
+    let cycle = match Cycle::find(&mut grid, gens) {
+        Some(cycle) => cycle,
+        None => {
+            println!("no period found within {} generations", gens);
+            return Ok(());
+        }
+    };
+    let repeating = &cycle.populations[cycle.start..cycle.start + cycle.period];
+    println!("period: {}", cycle.period);
+    println!("settled after: {} generations", cycle.start);
+    println!(
+        "population range: {}..={}",
+        repeating.iter().min().unwrap_or(&0),
+        repeating.iter().max().unwrap_or(&0)
+    );
+    println!("type: {}", cycle.kind());
+    if let Some(speed) = cycle.speed() {
+        let (dx, dy) = cycle.displacement;
+        println!("displacement: ({}, {})", dx, dy);
+        println!("speed: {}", speed);
+    }
+    Ok(())
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn render(args: &Args) -> Result<()> {
+    let pattern = read_pattern(args.input(0)?)?;
+    let output = args.input(1)?;
+    let mut grid = load_grid(args, &pattern, args.number("margin", 20)?)?;
+    for _ in 0..args.number("gen", 0)? {
+        grid.step_forward();
+    }
+
+    if output.ends_with(".svg") {
+        let mut style = SvgStyle::new();
+        style.set_cell_px(args.number("cell", 10)?);
+        style.set_phases(args.number("phases", 1)?);
+        style.set_labels(args.number("labels", 0)?);
+        style.set_grid_lines(args.flag("grid"));
+        let svg = grid.region_to_svg(0, 0, grid.width(), grid.height(), &style);
+        fs::write(output, svg)?;
+    } else {
+        let bytes = grid.to_png(args.number("cell", 4)?, &[])?;
+        fs::write(output, bytes)?;
+    }
+    Ok(())
+}
+
+// Writes an APNG for `.png` outputs and a GIF otherwise.
+fn record(args: &Args) -> Result<()> {
+    let pattern = read_pattern(args.input(0)?)?;
+    let output = args.input(1)?;
+    let mut grid = load_grid(args, &pattern, args.number("margin", 20)?)?;
+    let format = if output.ends_with(".png") {
+        AnimationFormat::Apng
+    } else {
+        AnimationFormat::Gif
+    };
+    let mut animation = Animation::new(format);
+    animation.set_cell_px(args.number("cell", 4)?);
+    animation.set_delay_ms(args.number("delay", 100)? as u32);
+
+    let bytes = grid.record_animation(args.number("gens", 300)?, &animation)?;
+    fs::write(output, bytes)?;
+    Ok(())
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let command = args.next().unwrap_or_default();
+    let result = Args::parse(args).and_then(|args| match command.as_str() {
+        "run" => run(&args),
+        "convert" => convert(&args),
+        "analyze" => analyze(&args),
+        "render" => render(&args),
+        "record" => record(&args),
+        _ => Err(USAGE.into()),
+    });
+
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cycle(rle: &str) -> Cycle {
+        let args = Args::parse(["--rule", "B3/S23"].iter().map(|arg| arg.to_string())).unwrap();
+        let pattern = PatternFile {
+            cells: parse_rle(rle, (100, 100)).unwrap(),
+            rule: None,
+        };
+        let mut grid = load_grid(&args, &pattern, 10).unwrap();
+        Cycle::find(&mut grid, 20).unwrap()
+    }
+
+    #[test]
+    fn classify_test() {
+        let glider = cycle("bo$2bo$3o!");
+        assert_eq!((glider.period, glider.kind()), (4, "spaceship"));
+        assert_eq!(glider.displacement, (1, 1));
+        assert_eq!(glider.speed().as_deref(), Some("c/4"));
+
+        let blinker = cycle("3o!");
+        assert_eq!((blinker.start, blinker.period), (0, 2));
+        assert_eq!((blinker.kind(), blinker.speed()), ("oscillator", None));
+
+        assert_eq!(cycle("2o$2o!").kind(), "still life");
+        assert_eq!(cycle("o!").kind(), "dies out");
+    }
+
+    #[test]
+    fn normalise_and_gcd_test() {
+        let (shape, origin) = normalise(&[(5, 7), (6, 9)]);
+        assert_eq!(shape, vec![(0, 0), (1, 2)]);
+        assert_eq!(origin, (5, 7));
+        assert_eq!(gcd(4, 6), 2);
+        assert_eq!(gcd(3, 0), 3);
+    }
+
+    #[test]
+    fn grid_limit_test() {
+        let args = Args::parse(std::iter::empty()).unwrap();
+        let pattern = PatternFile {
+            cells: vec![(0, 0), (1 << 14, 1 << 14)],
+            rule: None,
+        };
+        assert!(load_grid(&args, &pattern, 0).is_err());
+        let small = PatternFile {
+            cells: vec![(0, 0)],
+            rule: None,
+        };
+        assert!(load_grid(&args, &small, usize::MAX / 2).is_err());
+        assert_eq!(load_grid(&args, &small, 2).unwrap().width(), 5);
+    }
+}
@@ -0,0 +1,309 @@
+use std::ops::{BitAnd, BitOr, BitXor, Not};
+
+/// Two state grid packed 64 cells to a `u64`, row by row. Bits past the
+/// right edge are always zero and cells outside the grid count as dead.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    stride: usize,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        let stride = width.div_ceil(64);
+        Self {
+            width,
+            height,
+            stride,
+            words: vec![0; stride * height],
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.words[y * self.stride + x / 64] & (1 << (x % 64)) != 0
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, alive: bool) {
+        let word = &mut self.words[y * self.stride + x / 64];
+        if alive {
+            *word |= 1 << (x % 64);
+        } else {
+            *word &= !(1 << (x % 64));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|word| *word == 0)
+    }
+
+    /// The next generation of an outer-totalistic rule. Bit n of `birth` and
+    /// `survive` is set when n live neighbours give birth or keep a cell
+    /// alive.
+    pub fn step(&self, birth: u16, survive: u16) -> Self {
+        let mut next = Self::new(self.width, self.height);
+        if self.stride == 0 {
+            return next;
+        }
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+
+            next.words
+                .par_chunks_mut(self.stride)
+                .enumerate()
+                .for_each(|(y, row)| self.step_row(y, row, birth, survive));
+        }
+        #[cfg(not(feature = "parallel"))]
+        for (y, row) in next.words.chunks_mut(self.stride).enumerate() {
+            self.step_row(y, row, birth, survive);
+        }
+
+        next
+    }
+
+    fn step_row(&self, y: usize, row: &mut [u64], birth: u16, survive: u16) {
+        let inputs: Vec<_> = (0..self.stride).map(|i| self.neighbourhood(y, i)).collect();
+        evaluate(&inputs, birth, survive, row);
+        if !self.width.is_multiple_of(64) {
+            if let Some(last) = row.last_mut() {
+                *last &= (1 << (self.width % 64)) - 1;
+            }
+        }
+    }
+
+    fn word(&self, y: Option<usize>, i: Option<usize>) -> u64 {
+        match (y, i) {
+            (Some(y), Some(i)) if y < self.height && i < self.stride => {
+                self.words[y * self.stride + i]
+            }
+            _ => 0,
+        }
+    }
+
+    // The centre word and the eight words holding each cell's neighbours,
+    // shifted so that bit n lines up with cell n of the centre word.
+    fn neighbourhood(&self, y: usize, i: usize) -> (u64, [u64; 8]) {
+        let row = |y: Option<usize>| {
+            let word = self.word(y, Some(i));
+            let west = word << 1 | self.word(y, i.checked_sub(1)) >> 63;
+            let east = word >> 1 | self.word(y, Some(i + 1)) << 63;
+            (west, word, east)
+        };
+        let (nw, n, ne) = row(y.checked_sub(1));
+        let (w, centre, e) = row(Some(y));
+        let (sw, s, se) = row(Some(y + 1));
+        (centre, [nw, n, ne, w, e, sw, s, se])
+    }
+}
+
+/// Bitwise operations on a bundle of cells, one per bit.
+trait Lanes:
+    Copy + BitAnd<Output = Self> + BitOr<Output = Self> + BitXor<Output = Self> + Not<Output = Self>
+{
+    fn zero() -> Self;
+}
+
+impl Lanes for u64 {
+    fn zero() -> Self {
+        0
+    }
+}
+
+fn half_adder<T: Lanes>(a: T, b: T) -> (T, T) {
+    (a ^ b, a & b)
+}
+
+fn full_adder<T: Lanes>(a: T, b: T, c: T) -> (T, T) {
+    let partial = a ^ b;
+    (partial ^ c, a & b | c & partial)
+}
+
+// Adds up the eight neighbour bits of every cell into a four bit count and
+// looks each count up in the rule.
+fn next_state<T: Lanes>(centre: T, n: [T; 8], birth: u16, survive: u16) -> T {
+    let (a, a_carry) = full_adder(n[0], n[1], n[2]);
+    let (b, b_carry) = full_adder(n[3], n[4], n[5]);
+    let (c, c_carry) = half_adder(n[6], n[7]);
+    let (ones, ones_carry) = full_adder(a, b, c);
+    let (partial, partial_carry) = full_adder(a_carry, b_carry, c_carry);
+    let (twos, twos_carry) = half_adder(partial, ones_carry);
+    let (fours, eights) = half_adder(partial_carry, twos_carry);
+
+    let bits = [ones, twos, fours, eights];
+    let mut born = T::zero();
+    let mut survives = T::zero();
+    for count in 0..=8 {
+        let matches = bits
+            .iter()
+            .enumerate()
+            .map(|(bit, lanes)| {
+                if count & (1 << bit) != 0 {
+                    *lanes
+                } else {
+                    !*lanes
+                }
+            })
+            .fold(!T::zero(), |all, lanes| all & lanes);
+        if birth & (1 << count) != 0 {
+            born = born | matches;
+        }
+        if survive & (1 << count) != 0 {
+            survives = survives | matches;
+        }
+    }
+
+    centre & survives | !centre & born
+}
+
+#[cfg(not(all(
+    feature = "simd128",
+    target_arch = "wasm32",
+    target_feature = "simd128"
+)))]
+fn evaluate(inputs: &[(u64, [u64; 8])], birth: u16, survive: u16, out: &mut [u64]) {
+    for (word, (centre, neighbours)) in out.iter_mut().zip(inputs) {
+        *word = next_state(*centre, *neighbours, birth, survive);
+    }
+}
+
+// With the `simd128` feature (and `-C target-feature=+simd128`) two words are
+// evaluated per 128 bit vector.
+#[cfg(all(
+    feature = "simd128",
+    target_arch = "wasm32",
+    target_feature = "simd128"
+))]
+fn evaluate(inputs: &[(u64, [u64; 8])], birth: u16, survive: u16, out: &mut [u64]) {
+    use simd::V128;
+
+    let mut pairs = out.chunks_exact_mut(2).zip(inputs.chunks_exact(2));
+    for (words, pair) in &mut pairs {
+        let centre = V128::new(pair[0].0, pair[1].0);
+        let mut neighbours = [V128::zero(); 8];
+        for (i, lanes) in neighbours.iter_mut().enumerate() {
+            *lanes = V128::new(pair[0].1[i], pair[1].1[i]);
+        }
+        let (low, high) = next_state(centre, neighbours, birth, survive).words();
+        words[0] = low;
+        words[1] = high;
+    }
+    if out.len() % 2 == 1 {
+        let (centre, neighbours) = inputs[inputs.len() - 1];
+        out[out.len() - 1] = next_state(centre, neighbours, birth, survive);
+    }
+}
+
+#[cfg(all(
+    feature = "simd128",
+    target_arch = "wasm32",
+    target_feature = "simd128"
+))]
+mod simd {
+    use std::arch::wasm32::*;
+    use std::ops::{BitAnd, BitOr, BitXor, Not};
+
+    use super::Lanes;
+
+    #[derive(Copy, Clone)]
+    pub struct V128(v128);
+
+    impl V128 {
+        pub fn new(low: u64, high: u64) -> Self {
+            V128(u64x2(low, high))
+        }
+
+        pub fn words(self) -> (u64, u64) {
+            (
+                u64x2_extract_lane::<0>(self.0),
+                u64x2_extract_lane::<1>(self.0),
+            )
+        }
+    }
+
+    impl Lanes for V128 {
+        fn zero() -> Self {
+            V128(u64x2_splat(0))
+        }
+    }
+
+    impl BitAnd for V128 {
+        type Output = Self;
+
+        fn bitand(self, rhs: Self) -> Self {
+            V128(v128_and(self.0, rhs.0))
+        }
+    }
+
+    impl BitOr for V128 {
+        type Output = Self;
+
+        fn bitor(self, rhs: Self) -> Self {
+            V128(v128_or(self.0, rhs.0))
+        }
+    }
+
+    impl BitXor for V128 {
+        type Output = Self;
+
+        fn bitxor(self, rhs: Self) -> Self {
+            V128(v128_xor(self.0, rhs.0))
+        }
+    }
+
+    impl Not for V128 {
+        type Output = Self;
+
+        fn not(self) -> Self {
+            V128(v128_not(self.0))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIFE_BIRTH: u16 = 1 << 3;
+    const LIFE_SURVIVE: u16 = 1 << 2 | 1 << 3;
+
+    #[test]
+    fn blinker_across_word_boundary_test() {
+        let mut grid = BitGrid::new(130, 5);
+        for x in 63..66 {
+            grid.set(x, 2, true);
+        }
+        let next = grid.step(LIFE_BIRTH, LIFE_SURVIVE);
+        for y in 1..4 {
+            assert!(next.get(64, y));
+        }
+        assert!(!next.get(63, 2));
+        assert!(!next.get(65, 2));
+        assert_eq!(next.step(LIFE_BIRTH, LIFE_SURVIVE), grid);
+    }
+
+    #[test]
+    fn count_matches_every_total_test() {
+        // Cell (1, 1) gets n live neighbours and is born only on exactly n.
+        for n in 0..=8u16 {
+            let mut grid = BitGrid::new(3, 3);
+            let ring = [
+                (0, 0),
+                (1, 0),
+                (2, 0),
+                (0, 1),
+                (2, 1),
+                (0, 2),
+                (1, 2),
+                (2, 2),
+            ];
+            for (x, y) in ring.iter().take(n as usize) {
+                grid.set(*x, *y, true);
+            }
+            assert!(grid.step(1 << n, 0).get(1, 1), "{} neighbours", n);
+            assert!(!grid.step(!(1 << n), 0).get(1, 1), "{} neighbours", n);
+        }
+    }
+}
@@ -0,0 +1,463 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::grid::Grid;
+use crate::life_rule::LifeRule;
+use crate::rle;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ChallengeError {
+    InvalidLine(usize),
+    MissingField(&'static str),
+    OutsideRegion,
+    OverBudget,
+}
+
+impl Display for ChallengeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChallengeError::InvalidLine(line) => write!(f, "invalid challenge line {}", line),
+            ChallengeError::MissingField(field) => write!(f, "challenge is missing `{}`", field),
+            ChallengeError::OutsideRegion => write!(f, "cell is outside the editable region"),
+            ChallengeError::OverBudget => write!(f, "no cells left in the budget"),
+        }
+    }
+}
+
+impl std::error::Error for ChallengeError {}
+
+#[cfg(feature = "wasm")]
+impl From<ChallengeError> for JsValue {
+    fn from(error: ChallengeError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn holds(&self, left: usize, right: usize) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Equal => left == right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Greater => left > right,
+        }
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "<" => Ok(Comparison::Less),
+            "<=" => Ok(Comparison::LessOrEqual),
+            "==" | "=" => Ok(Comparison::Equal),
+            ">=" => Ok(Comparison::GreaterOrEqual),
+            ">" => Ok(Comparison::Greater),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Goal {
+    Empty,
+    Population(Comparison, usize),
+    /// The pattern's bounding box at (x, y) matches exactly and the ring of
+    /// cells around it is dead.
+    Pattern {
+        x: usize,
+        y: usize,
+        cells: Vec<(usize, usize)>,
+    },
+}
+
+impl Goal {
+    fn is_met(&self, grid: &Grid) -> bool {
+        match self {
+            Goal::Empty => grid.population() == 0,
+            Goal::Population(comparison, count) => comparison.holds(grid.population(), *count),
+            Goal::Pattern { x, y, cells } => {
+                let width = cells.iter().map(|cell| cell.0 + 1).max().unwrap_or(0);
+                let height = cells.iter().map(|cell| cell.1 + 1).max().unwrap_or(0);
+                for dy in 0..height + 2 {
+                    for dx in 0..width + 2 {
+                        let expected = dx > 0 && dy > 0 && cells.contains(&(dx - 1, dy - 1));
+                        let alive = x
+                            .checked_add(dx)
+                            .and_then(|cx| cx.checked_sub(1))
+                            .zip(y.checked_add(dy).and_then(|cy| cy.checked_sub(1)))
+                            .is_some_and(|(cx, cy)| grid.is_alive(cx, cy));
+                        if alive != expected {
+                            return false;
+                        }
+                    }
+                }
+                true
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Evaluation {
+    pub passed: bool,
+    pub generation: Option<usize>,
+}
+
+/// A puzzle: an initial pattern, a rectangle the player may add cells to, how
+/// many cells they may add, and a goal that has to be reached within a number
+/// of generations. Definitions are `key: value` lines, e.g.
+///
+/// ```text
+/// size: 20 20
+/// pattern: 5 5 bo$2bo$3o!
+/// region: 0 0 20 4
+/// budget: 3
+/// generations: 50
+/// goal: empty
+/// ```
+///
+/// `goal` is one of `empty`, `population <op> <count>` or
+/// `pattern <x> <y> <rle>`. `pattern` may be given more than once. An
+/// optional `rule: B3/S23` line picks the rule, otherwise the grid's default
+/// is used.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct Challenge {
+    size: (usize, usize),
+    rule: LifeRule,
+    initial: Vec<(usize, usize)>,
+    region: (usize, usize, usize, usize),
+    budget: usize,
+    generations: usize,
+    goal: Goal,
+    additions: Vec<(usize, usize)>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Challenge {
+    pub fn parse(definition: &str) -> Result<Challenge, ChallengeError> {
+        definition.parse()
+    }
+
+    pub fn rule(&self) -> String {
+        self.rule.to_string()
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    pub fn remaining_budget(&self) -> usize {
+        self.budget - self.additions.len()
+    }
+
+    pub fn generations(&self) -> usize {
+        self.generations
+    }
+
+    pub fn add_cell(&mut self, x: usize, y: usize) -> Result<(), ChallengeError> {
+        let (region_x, region_y, width, height) = self.region;
+        let inside = |start: usize, length: usize, at: usize| {
+            at >= start && start.checked_add(length).is_none_or(|end| at < end)
+        };
+        if !inside(region_x, width, x) || !inside(region_y, height, y) {
+            return Err(ChallengeError::OutsideRegion);
+        }
+        if self.additions.contains(&(x, y)) {
+            return Ok(());
+        }
+        if self.additions.len() >= self.budget {
+            return Err(ChallengeError::OverBudget);
+        }
+
+        self.additions.push((x, y));
+        Ok(())
+    }
+
+    pub fn remove_cell(&mut self, x: usize, y: usize) {
+        self.additions.retain(|cell| *cell != (x, y));
+    }
+
+    pub fn clear(&mut self) {
+        self.additions.clear();
+    }
+
+    /// The starting grid with the player's additions applied.
+    pub fn grid(&self) -> Grid {
+        let mut grid = Grid::new(self.size.0, self.size.1);
+        grid.set_life_rule(self.rule);
+        grid.revive_cells(self.initial.clone(), 0);
+        grid.revive_cells(self.additions.clone(), 0);
+        grid
+    }
+
+    pub fn evaluate(&self) -> Evaluation {
+        let mut grid = self.grid();
+        for generation in 0..=self.generations {
+            if self.goal.is_met(&grid) {
+                return Evaluation {
+                    passed: true,
+                    generation: Some(generation),
+                };
+            }
+            grid.step_forward();
+        }
+
+        Evaluation {
+            passed: false,
+            generation: None,
+        }
+    }
+}
+
+impl FromStr for Challenge {
+    type Err = ChallengeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut size = None;
+        let mut rule = LifeRule::default();
+        // Line, x, y and RLE; parsed once the size is known.
+        let mut patterns = vec![];
+        let mut region = None;
+        let mut budget = None;
+        let mut generations = None;
+        let mut goal = None;
+        let mut goal_pattern = None;
+
+        for (index, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = ChallengeError::InvalidLine(index + 1);
+            let (key, value) = line.split_once(':').ok_or_else(|| invalid.clone())?;
+            let words: Vec<&str> = value.split_whitespace().collect();
+            let number = |i: usize| -> Result<usize, ChallengeError> {
+                words
+                    .get(i)
+                    .and_then(|word| word.parse().ok())
+                    .ok_or_else(|| invalid.clone())
+            };
+
+            match key.trim() {
+                "size" => size = Some((number(0)?, number(1)?)),
+                "rule" => rule = value.trim().parse().map_err(|_| invalid.clone())?,
+                "pattern" => {
+                    let rle = words.get(2).ok_or_else(|| invalid.clone())?;
+                    patterns.push((index + 1, number(0)?, number(1)?, *rle));
+                }
+                "region" => {
+                    let rectangle = (number(0)?, number(1)?, number(2)?, number(3)?);
+                    region = Some((index + 1, rectangle));
+                }
+                "budget" => budget = Some(number(0)?),
+                "generations" => generations = Some(number(0)?),
+                "goal" => {
+                    goal = Some(match words.first() {
+                        Some(&"empty") => Goal::Empty,
+                        Some(&"population") => {
+                            let comparison = words
+                                .get(1)
+                                .and_then(|word| word.parse().ok())
+                                .ok_or_else(|| invalid.clone())?;
+                            Goal::Population(comparison, number(2)?)
+                        }
+                        Some(&"pattern") => {
+                            let rle = words.get(3).ok_or_else(|| invalid.clone())?;
+                            goal_pattern = Some((index + 1, *rle));
+                            Goal::Pattern {
+                                x: number(1)?,
+                                y: number(2)?,
+                                cells: vec![],
+                            }
+                        }
+                        _ => return Err(invalid),
+                    })
+                }
+                _ => return Err(invalid),
+            }
+        }
+
+        let size = size.ok_or(ChallengeError::MissingField("size"))?;
+        let budget = budget.ok_or(ChallengeError::MissingField("budget"))?;
+        let generations = generations.ok_or(ChallengeError::MissingField("generations"))?;
+        // Cells of a pattern at (x, y), which has to fit inside the grid.
+        let parse = |line: usize, x: usize, y: usize, text: &str| {
+            let bounds = size.0.checked_sub(x).zip(size.1.checked_sub(y));
+            bounds
+                .and_then(|bounds| rle::parse_rle(text, bounds).ok())
+                .ok_or(ChallengeError::InvalidLine(line))
+        };
+        let region = match region {
+            Some((line, (x, y, width, height))) => {
+                let fits = |start: usize, length: usize, side: usize| {
+                    start.checked_add(length).is_some_and(|end| end <= side)
+                };
+                if !fits(x, width, size.0) || !fits(y, height, size.1) {
+                    return Err(ChallengeError::InvalidLine(line));
+                }
+                (x, y, width, height)
+            }
+            None => (0, 0, size.0, size.1),
+        };
+        let mut initial = vec![];
+        for (line, x, y, text) in patterns {
+            initial.extend(
+                parse(line, x, y, text)?
+                    .into_iter()
+                    .map(|(dx, dy)| (x + dx, y + dy)),
+            );
+        }
+        let mut goal = goal.ok_or(ChallengeError::MissingField("goal"))?;
+        if let (Goal::Pattern { x, y, cells }, Some((line, text))) = (&mut goal, goal_pattern) {
+            *cells = parse(line, *x, *y, text)?;
+        }
+        Ok(Challenge {
+            size,
+            rule,
+            initial,
+            region,
+            budget,
+            generations,
+            goal,
+            additions: vec![],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KILL_BLOCK: &str = "
+        # kill the block by adding a cell
+        size: 10 10
+        pattern: 4 4 2o$2o!
+        region: 0 0 10 10
+        budget: 1
+        generations: 10
+        goal: empty
+    ";
+
+    #[test]
+    fn parse_challenge_test() {
+        let challenge = Challenge::parse(KILL_BLOCK).unwrap();
+        assert_eq!(challenge.size, (10, 10));
+        assert_eq!(challenge.initial.len(), 4);
+        assert_eq!(challenge.goal, Goal::Empty);
+        assert_eq!(
+            Challenge::parse("size: 10 10\nbudget: 1").err(),
+            Some(ChallengeError::MissingField("generations"))
+        );
+        assert_eq!(
+            Challenge::parse("size: 10").err(),
+            Some(ChallengeError::InvalidLine(1))
+        );
+    }
+
+    #[test]
+    fn budget_and_region_test() {
+        let mut challenge = Challenge::parse(&KILL_BLOCK.replace("0 0 10 10", "0 0 5 5")).unwrap();
+        assert_eq!(challenge.add_cell(7, 7), Err(ChallengeError::OutsideRegion));
+        assert_eq!(challenge.add_cell(1, 1), Ok(()));
+        assert_eq!(challenge.add_cell(2, 1), Err(ChallengeError::OverBudget));
+        assert_eq!(challenge.remaining_budget(), 0);
+
+        // Regions, patterns and goals have to fit inside the grid.
+        let huge = KILL_BLOCK.replace("0 0 10 10", "5 5 18446744073709551615 1");
+        assert_eq!(
+            Challenge::parse(&huge).err(),
+            Some(ChallengeError::InvalidLine(5))
+        );
+        assert_eq!(
+            Challenge::parse(&KILL_BLOCK.replace("0 0 10 10", "0 0 10 11")).err(),
+            Some(ChallengeError::InvalidLine(5))
+        );
+        assert_eq!(
+            Challenge::parse(&KILL_BLOCK.replace("4 4 2o", "9 4 2o")).err(),
+            Some(ChallengeError::InvalidLine(4))
+        );
+        let far_goal = KILL_BLOCK.replace("goal: empty", "goal: pattern 18446744073709551615 0 o!");
+        assert_eq!(
+            Challenge::parse(&far_goal).err(),
+            Some(ChallengeError::InvalidLine(8))
+        );
+
+        let mut challenge = Challenge::parse(KILL_BLOCK).unwrap();
+        challenge.region = (5, 5, usize::MAX, 1);
+        assert_eq!(challenge.add_cell(6, 5), Ok(()));
+        assert_eq!(challenge.add_cell(6, 6), Err(ChallengeError::OutsideRegion));
+    }
+
+    #[test]
+    fn evaluate_challenge_test() {
+        let mut challenge = Challenge::parse(&KILL_BLOCK.replace("2o$2o!", "o!")).unwrap();
+        assert_eq!(
+            challenge.evaluate(),
+            Evaluation {
+                passed: true,
+                generation: Some(1)
+            }
+        );
+        challenge.add_cell(5, 4).unwrap();
+        assert_eq!(
+            challenge.evaluate(),
+            Evaluation {
+                passed: false,
+                generation: None
+            }
+        );
+
+        let still = KILL_BLOCK.replace("goal: empty", "goal: pattern 4 4 2o$2o!");
+        assert_eq!(
+            Challenge::parse(&still).unwrap().evaluate().generation,
+            Some(0)
+        );
+
+        let crowded = KILL_BLOCK.replace("goal: empty", "goal: population >= 5");
+        assert!(!Challenge::parse(&crowded).unwrap().evaluate().passed);
+    }
+
+    #[test]
+    fn rule_test() {
+        // A glider moves one cell diagonally every four generations in Life.
+        let glider = "
+            size: 12 12
+            rule: B3/S23
+            pattern: 2 2 bo$2bo$3o!
+            budget: 0
+            generations: 4
+            goal: pattern 3 3 bo$2bo$3o!
+        ";
+        let challenge = Challenge::parse(glider).unwrap();
+        assert_eq!(challenge.rule(), "B3/S23");
+        assert_eq!(challenge.evaluate().generation, Some(4));
+        let default = glider.replace("rule: B3/S23", "");
+        assert!(!Challenge::parse(&default).unwrap().evaluate().passed);
+
+        assert_eq!(
+            Challenge::parse(&glider.replace("B3/S23", "B9")).err(),
+            Some(ChallengeError::InvalidLine(3))
+        );
+        assert_eq!(
+            Challenge::parse(&glider.replace("2 2 bo", "2 2 99o")).err(),
+            Some(ChallengeError::InvalidLine(4))
+        );
+    }
+}
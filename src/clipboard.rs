@@ -0,0 +1,216 @@
+use std::fmt::{Display, Formatter};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ClipboardError {
+    /// The expected and the given number of states.
+    WrongLength(usize, usize),
+}
+
+impl Display for ClipboardError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClipboardError::WrongLength(expected, found) => {
+                write!(f, "clipboard needs {} states, got {}", expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ClipboardError {}
+
+#[cfg(feature = "wasm")]
+impl From<ClipboardError> for JsValue {
+    fn from(error: ClipboardError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+/// Applied to the clipboard when pasting. Rotations are clockwise.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Transform {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Mirrors left and right.
+    FlipHorizontal,
+    /// Mirrors top and bottom.
+    FlipVertical,
+}
+
+/// How pasted cells combine with the cells already on the grid.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PasteMode {
+    /// Live clipboard cells are added, nothing is removed.
+    Or,
+    /// Only cells live in both stay alive.
+    And,
+    /// Live clipboard cells toggle the cell under them.
+    Xor,
+    /// The clipboard rectangle replaces what was there, dead cells included.
+    Copy,
+}
+
+impl PasteMode {
+    /// The state of a cell with `target` under it after pasting `source`.
+    pub fn apply(self, target: u8, source: u8) -> u8 {
+        match self {
+            PasteMode::Or if source != 0 => source,
+            PasteMode::And if source == 0 => 0,
+            PasteMode::Xor if source != 0 => {
+                if target == 0 {
+                    source
+                } else {
+                    0
+                }
+            }
+            PasteMode::Copy => source,
+            _ => target,
+        }
+    }
+}
+
+/// A copied rectangle of cell states, row-major, 0 for dead and colour + 1
+/// for live cells.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Clipboard {
+    width: usize,
+    height: usize,
+    states: Vec<u8>,
+}
+
+impl Clipboard {
+    /// Fails unless there are `width` * `height` states.
+    pub fn new(width: usize, height: usize, states: Vec<u8>) -> Result<Self, ClipboardError> {
+        match width.checked_mul(height) {
+            Some(length) if length == states.len() => Ok(Self {
+                width,
+                height,
+                states,
+            }),
+            expected => Err(ClipboardError::WrongLength(
+                expected.unwrap_or(usize::MAX),
+                states.len(),
+            )),
+        }
+    }
+
+    /// Live colour 0 cells at the given offsets, in the smallest rectangle
+    /// that holds them.
+    pub fn from_cells(cells: &[(usize, usize)]) -> Self {
+        let width = cells.iter().map(|cell| cell.0 + 1).max().unwrap_or(0);
+        let height = cells.iter().map(|cell| cell.1 + 1).max().unwrap_or(0);
+        let mut states = vec![0; width * height];
+        for (x, y) in cells {
+            states[y * width + x] = 1;
+        }
+        Self {
+            width,
+            height,
+            states,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn state(&self, x: usize, y: usize) -> u8 {
+        self.states[y * self.width + x]
+    }
+
+    pub fn live_cells(&self) -> Vec<(usize, usize)> {
+        (0..self.states.len())
+            .filter(|i| self.states[*i] != 0)
+            .map(|i| (i % self.width, i / self.width))
+            .collect()
+    }
+
+    pub fn transformed(&self, transform: Transform) -> Self {
+        let (w, h) = (self.width, self.height);
+        let (width, height) = match transform {
+            Transform::Rotate90 | Transform::Rotate270 => (h, w),
+            _ => (w, h),
+        };
+        let mut states = vec![0; width * height];
+        for y in 0..h {
+            for x in 0..w {
+                let (nx, ny) = match transform {
+                    Transform::Identity => (x, y),
+                    Transform::Rotate90 => (h - 1 - y, x),
+                    Transform::Rotate180 => (w - 1 - x, h - 1 - y),
+                    Transform::Rotate270 => (y, w - 1 - x),
+                    Transform::FlipHorizontal => (w - 1 - x, y),
+                    Transform::FlipVertical => (x, h - 1 - y),
+                };
+                states[ny * width + nx] = self.state(x, y);
+            }
+        }
+        Self {
+            width,
+            height,
+            states,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transform_test() {
+        // xx.
+        // ...
+        let clipboard = Clipboard::new(3, 2, vec![1, 2, 0, 0, 0, 0]).unwrap();
+        assert_eq!(
+            Clipboard::new(3, 2, vec![1]),
+            Err(ClipboardError::WrongLength(6, 1))
+        );
+        assert!(Clipboard::new(usize::MAX, 2, vec![]).is_err());
+        let rotated = clipboard.transformed(Transform::Rotate90);
+        assert_eq!((rotated.width(), rotated.height()), (2, 3));
+        assert_eq!(rotated.live_cells(), vec![(1, 0), (1, 1)]);
+        assert_eq!(rotated.state(1, 1), 2);
+        assert_eq!(rotated.transformed(Transform::Rotate270), clipboard);
+        assert_eq!(
+            clipboard.transformed(Transform::Rotate180).live_cells(),
+            vec![(1, 1), (2, 1)]
+        );
+        assert_eq!(
+            clipboard
+                .transformed(Transform::FlipHorizontal)
+                .live_cells(),
+            vec![(1, 0), (2, 0)]
+        );
+        assert_eq!(
+            clipboard.transformed(Transform::FlipVertical).live_cells(),
+            vec![(0, 1), (1, 1)]
+        );
+    }
+
+    #[test]
+    fn paste_mode_test() {
+        let table = |mode: PasteMode| {
+            [(0, 0), (0, 2), (3, 0), (3, 2)]
+                .iter()
+                .map(|(target, source)| mode.apply(*target, *source))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(table(PasteMode::Or), vec![0, 2, 3, 2]);
+        assert_eq!(table(PasteMode::And), vec![0, 0, 0, 3]);
+        assert_eq!(table(PasteMode::Xor), vec![0, 2, 3, 0]);
+        assert_eq!(table(PasteMode::Copy), vec![0, 2, 0, 2]);
+    }
+}
@@ -0,0 +1,212 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// What a drawing operation does to each cell it covers.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DrawMode {
+    /// Sets the cell to the drawing state.
+    Set,
+    /// Kills the cell.
+    Clear,
+    /// Kills live cells and sets dead ones to the drawing state.
+    Toggle,
+}
+
+impl DrawMode {
+    /// The new state of a cell in `current` drawn over with `state`.
+    pub fn apply(self, current: u8, state: u8) -> u8 {
+        match self {
+            DrawMode::Set => state,
+            DrawMode::Clear => 0,
+            DrawMode::Toggle if current == 0 => state,
+            DrawMode::Toggle => 0,
+        }
+    }
+}
+
+/// The cells of a Bresenham line inside a `size` grid, both ends included.
+/// The line is clipped to the grid first, so far away ends cost nothing.
+pub fn line(from: (usize, usize), to: (usize, usize), size: (usize, usize)) -> Vec<(usize, usize)> {
+    let (from, to) = match clip_line(from, to, size) {
+        Some(ends) => ends,
+        None => return vec![],
+    };
+    let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+    let (sx, sy) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+    let (mut x, mut y) = from;
+    let mut error = dx + dy;
+    let mut points = vec![];
+    loop {
+        points.push((x as usize, y as usize));
+        if (x, y) == to {
+            return points;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += sx;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += sy;
+        }
+    }
+}
+
+// Liang-Barsky: the part of the segment between the cell centres of the
+// grid, with its ends rounded to cells.
+fn clip_line(
+    from: (usize, usize),
+    to: (usize, usize),
+    size: (usize, usize),
+) -> Option<((i64, i64), (i64, i64))> {
+    let (x0, y0) = (from.0 as f64, from.1 as f64);
+    let (dx, dy) = (to.0 as f64 - x0, to.1 as f64 - y0);
+    let (right, bottom) = (size.0 as f64 - 1.0, size.1 as f64 - 1.0);
+    let (mut enter, mut leave) = (0.0f64, 1.0f64);
+    for (p, q) in [(-dx, x0), (dx, right - x0), (-dy, y0), (dy, bottom - y0)].iter() {
+        if *p == 0.0 {
+            if *q < 0.0 {
+                return None;
+            }
+        } else if *p < 0.0 {
+            enter = enter.max(q / p);
+        } else {
+            leave = leave.min(q / p);
+        }
+    }
+    if enter > leave {
+        return None;
+    }
+    let at = |t: f64| {
+        (
+            (x0 + t * dx).round().clamp(0.0, right) as i64,
+            (y0 + t * dy).round().clamp(0.0, bottom) as i64,
+        )
+    };
+    Some((at(enter), at(leave)))
+}
+
+/// The cells of the `width` x `height` box from (`x`, `y`) that are inside
+/// a `size` grid.
+pub fn rectangle(
+    (x, y): (usize, usize),
+    (width, height): (usize, usize),
+    filled: bool,
+    size: (usize, usize),
+) -> Vec<(usize, usize)> {
+    let (right, bottom) = (x.saturating_add(width), y.saturating_add(height));
+    let mut points = vec![];
+    for j in y..bottom.min(size.1) {
+        for i in x..right.min(size.0) {
+            if filled || i == x || j == y || i == right - 1 || j == bottom - 1 {
+                points.push((i, j));
+            }
+        }
+    }
+    points
+}
+
+/// The ellipse filling the `width` x `height` box from (`x`, `y`), inside a
+/// `size` grid. Its outline is every cell of the filled ellipse with a
+/// side neighbour outside it, so each cell appears once and toggling is
+/// safe.
+pub fn ellipse(
+    (x, y): (usize, usize),
+    (width, height): (usize, usize),
+    filled: bool,
+    size: (usize, usize),
+) -> Vec<(usize, usize)> {
+    let (rx, ry) = (width as f64 / 2.0, height as f64 / 2.0);
+    let (cx, cy) = (x as f64 + rx - 0.5, y as f64 + ry - 0.5);
+    let inside = |i: f64, j: f64| {
+        let (u, v) = ((i - cx) / rx, (j - cy) / ry);
+        u * u + v * v <= 1.0
+    };
+    let (right, bottom) = (x.saturating_add(width), y.saturating_add(height));
+    let mut points = vec![];
+    for j in y..bottom.min(size.1) {
+        for i in x..right.min(size.0) {
+            let (fi, fj) = (i as f64, j as f64);
+            let edge = [(-1.0, 0.0), (1.0, 0.0), (0.0, -1.0), (0.0, 1.0)]
+                .iter()
+                .any(|(di, dj)| !inside(fi + di, fj + dj));
+            if inside(fi, fj) && (filled || edge) {
+                points.push((i, j));
+            }
+        }
+    }
+    points
+}
+
+/// `(x, y, width, height)` clamped to a `grid_width` x `grid_height` grid.
+pub fn clip_region(
+    region: (usize, usize, usize, usize),
+    grid_width: usize,
+    grid_height: usize,
+) -> (usize, usize, usize, usize) {
+    let (x, y, width, height) = region;
+    let (x, y) = (x.min(grid_width), y.min(grid_height));
+    (x, y, width.min(grid_width - x), height.min(grid_height - y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: (usize, usize) = (20, 20);
+
+    #[test]
+    fn line_test() {
+        assert_eq!(
+            line((0, 0), (4, 2), SIZE),
+            vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]
+        );
+        assert_eq!(
+            line((2, 3), (2, 0), SIZE),
+            vec![(2, 3), (2, 2), (2, 1), (2, 0)]
+        );
+        assert_eq!(line((1, 1), (1, 1), SIZE), vec![(1, 1)]);
+
+        // Clipped to the grid before any cells are walked.
+        assert_eq!(line((0, 5), (usize::MAX, 5), SIZE).len(), 20);
+        let diagonal = line((0, 0), (1 << 40, 1 << 40), SIZE);
+        assert_eq!(diagonal.first(), Some(&(0, 0)));
+        assert_eq!(diagonal.last(), Some(&(19, 19)));
+        assert!(line((30, 0), (40, 10), SIZE).is_empty());
+    }
+
+    #[test]
+    fn shape_test() {
+        assert_eq!(rectangle((0, 0), (3, 3), false, SIZE).len(), 8);
+        assert_eq!(rectangle((0, 0), (3, 3), true, SIZE).len(), 9);
+        assert_eq!(rectangle((0, 0), (1, 4), false, SIZE).len(), 4);
+        // Only the left and top sides are on the grid.
+        assert_eq!(
+            rectangle((10, 10), (usize::MAX, 1 << 40), false, SIZE).len(),
+            19
+        );
+        assert_eq!(
+            rectangle((0, 0), (1_000_000, 1_000_000), true, SIZE).len(),
+            400
+        );
+
+        // .xxx.
+        // xxxxx
+        // xxxxx
+        // xxxxx
+        // .xxx.
+        let disc = ellipse((0, 0), (5, 5), true, SIZE);
+        assert_eq!(disc.len(), 21);
+        assert!(!disc.contains(&(0, 0)) && disc.contains(&(1, 0)));
+        assert_eq!(ellipse((0, 0), (5, 5), false, SIZE).len(), 12);
+        assert!(ellipse((0, 0), (1 << 40, 1 << 40), true, SIZE).len() <= 400);
+        let outline = ellipse((2, 5), (9, 5), false, SIZE);
+        assert!(outline.contains(&(2, 7)) && outline.contains(&(10, 7)));
+        assert!(!outline.contains(&(6, 7)));
+        assert_eq!(DrawMode::Toggle.apply(2, 3), 0);
+        assert_eq!(DrawMode::Toggle.apply(0, 3), 3);
+    }
+}
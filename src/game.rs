@@ -0,0 +1,258 @@
+use std::fmt::{Display, Formatter};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::cell_state::CellState;
+use crate::grid::Grid;
+use crate::pattern::Pattern;
+use crate::variant::Variant;
+
+const PLAYERS: u8 = 2;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GameError {
+    GameOver,
+    OutOfBounds,
+    OutsideTerritory,
+    Occupied,
+    OverBudget,
+}
+
+impl Display for GameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            GameError::GameOver => "the game is over",
+            GameError::OutOfBounds => "placement is outside the grid",
+            GameError::OutsideTerritory => "placement is outside the player's half",
+            GameError::Occupied => "cell is already alive",
+            GameError::OverBudget => "not enough cells left this turn",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+impl std::error::Error for GameError {}
+
+#[cfg(feature = "wasm")]
+impl From<GameError> for JsValue {
+    fn from(error: GameError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+/// Two player territory game played on an Immigration grid. Player 0 owns the
+/// left half and player 1 the right half. Each round both players place up to
+/// `budget` cells, then the grid runs `generations` steps. After the last
+/// round the player with the bigger surviving population wins.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Game {
+    grid: Grid,
+    player: u8,
+    budget: usize,
+    remaining: usize,
+    generations: usize,
+    rounds: usize,
+    round: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Game {
+    pub fn new(
+        size_x: usize,
+        size_y: usize,
+        budget: usize,
+        generations: usize,
+        rounds: usize,
+    ) -> Self {
+        let mut grid = Grid::new(size_x, size_y);
+        grid.set_variant(Variant::Immigration);
+
+        Self {
+            grid,
+            player: 0,
+            budget,
+            remaining: budget,
+            generations,
+            rounds,
+            round: 0,
+        }
+    }
+
+    /// 1 for live and 0 for dead cells, row by row.
+    pub fn get_cells(&self) -> Vec<u8> {
+        self.grid
+            .cells()
+            .map(|(_, _, cell)| (cell.state == CellState::Alive) as u8)
+            .collect()
+    }
+
+    pub fn get_colours(&self) -> Vec<u8> {
+        self.grid.get_colours()
+    }
+
+    pub fn width(&self) -> usize {
+        self.grid.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.grid.height()
+    }
+
+    pub fn current_player(&self) -> u8 {
+        self.player
+    }
+
+    pub fn remaining_budget(&self) -> usize {
+        self.remaining
+    }
+
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.round >= self.rounds
+    }
+
+    pub fn score(&self, player: u8) -> usize {
+        self.grid.colour_population(player)
+    }
+
+    /// The winning player once the game is finished, `None` while it is still
+    /// running or when it ended in a draw.
+    pub fn winner(&self) -> Option<u8> {
+        if !self.is_finished() {
+            return None;
+        }
+
+        let (left, right) = (self.score(0), self.score(1));
+        match left.cmp(&right) {
+            std::cmp::Ordering::Greater => Some(0),
+            std::cmp::Ordering::Less => Some(1),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    pub fn render(&self) -> String {
+        self.grid.render()
+    }
+
+    pub fn place_cell(&mut self, x: usize, y: usize) -> Result<(), GameError> {
+        self.place(vec![(x, y)])
+    }
+
+    pub fn place_pattern(&mut self, pattern: Pattern, x: usize, y: usize) -> Result<(), GameError> {
+        let mut scratch = Grid::new(self.grid.width(), self.grid.height());
+        scratch.spawn_pattern(pattern, x, y, self.player);
+        self.place(scratch.live_cells())
+    }
+
+    /// Hands the turn to the other player. Once both players have placed
+    /// their cells the grid is stepped and the next round begins.
+    pub fn end_turn(&mut self) -> Result<(), GameError> {
+        if self.is_finished() {
+            return Err(GameError::GameOver);
+        }
+
+        self.player = (self.player + 1) % PLAYERS;
+        self.remaining = self.budget;
+        if self.player == 0 {
+            for _ in 0..self.generations {
+                self.grid.step_forward();
+            }
+            self.round += 1;
+        }
+
+        Ok(())
+    }
+}
+
+impl Game {
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    fn place(&mut self, coords: Vec<(usize, usize)>) -> Result<(), GameError> {
+        if self.is_finished() {
+            return Err(GameError::GameOver);
+        }
+        if coords.is_empty()
+            || coords
+                .iter()
+                .any(|(x, y)| *x >= self.grid.width() || *y >= self.grid.height())
+        {
+            return Err(GameError::OutOfBounds);
+        }
+        if coords.iter().any(|(x, _)| !self.in_territory(*x)) {
+            return Err(GameError::OutsideTerritory);
+        }
+        if coords.iter().any(|(x, y)| self.grid.is_alive(*x, *y)) {
+            return Err(GameError::Occupied);
+        }
+        if coords.len() > self.remaining {
+            return Err(GameError::OverBudget);
+        }
+
+        self.remaining -= coords.len();
+        self.grid.revive_cells(coords, self.player);
+        Ok(())
+    }
+
+    fn in_territory(&self, x: usize) -> bool {
+        let half = self.grid.width() / 2;
+        match self.player {
+            0 => x < half,
+            _ => x >= half,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placement_checks_territory_test() {
+        let mut game = Game::new(20, 10, 5, 1, 1);
+        assert_eq!(game.place_cell(15, 5), Err(GameError::OutsideTerritory));
+        assert_eq!(game.place_cell(25, 5), Err(GameError::OutOfBounds));
+        assert_eq!(game.place_cell(5, 5), Ok(()));
+        assert_eq!(game.get_cells()[5 * 20 + 5], 1);
+        assert_eq!(game.get_cells().iter().sum::<u8>(), 1);
+        assert_eq!(game.place_cell(5, 5), Err(GameError::Occupied));
+        game.end_turn().unwrap();
+        assert_eq!(game.place_cell(5, 6), Err(GameError::OutsideTerritory));
+        assert_eq!(game.place_cell(15, 6), Ok(()));
+    }
+
+    #[test]
+    fn placement_checks_budget_test() {
+        let mut game = Game::new(20, 10, 5, 1, 1);
+        assert_eq!(
+            game.place_pattern(Pattern::LightSpaceship, 2, 2),
+            Err(GameError::OverBudget)
+        );
+        assert_eq!(game.place_pattern(Pattern::Block, 2, 2), Ok(()));
+        assert_eq!(game.remaining_budget(), 1);
+        assert_eq!(
+            game.place_pattern(Pattern::Block, 5, 5),
+            Err(GameError::OverBudget)
+        );
+    }
+
+    #[test]
+    fn game_reports_winner_test() {
+        let mut game = Game::new(20, 10, 4, 5, 1);
+        game.place_pattern(Pattern::Block, 2, 2).unwrap();
+        game.end_turn().unwrap();
+        game.place_cell(15, 5).unwrap();
+        game.end_turn().unwrap();
+
+        assert!(game.is_finished());
+        assert_eq!(game.score(0), 4);
+        assert_eq!(game.score(1), 0);
+        assert_eq!(game.winner(), Some(0));
+        assert_eq!(game.place_cell(2, 8), Err(GameError::GameOver));
+    }
+}
@@ -0,0 +1,676 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// Dense transition tables bigger than this (in entries) are refused.
+const MAX_TABLE_SIZE: usize = 1 << 24;
+
+// Marks table entries no transition matched; those cells keep their state.
+const UNCHANGED: u8 = u8::MAX;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RuleFileError {
+    MissingRule,
+    MissingTransitions,
+    UnsupportedSection(String),
+    UnsupportedNeighborhood(String),
+    UnsupportedSymmetry(String),
+    TooManyStates(usize),
+    InvalidLine(usize, String),
+}
+
+impl Display for RuleFileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleFileError::MissingRule => write!(f, "rule file has no @RULE line"),
+            RuleFileError::MissingTransitions => {
+                write!(f, "rule file needs a @TABLE or @TREE section")
+            }
+            RuleFileError::UnsupportedSection(section) => {
+                write!(f, "unsupported section {}", section)
+            }
+            RuleFileError::UnsupportedNeighborhood(neighborhood) => {
+                write!(f, "unsupported neighborhood {}", neighborhood)
+            }
+            RuleFileError::UnsupportedSymmetry(symmetry) => {
+                write!(f, "unsupported symmetries {}", symmetry)
+            }
+            RuleFileError::TooManyStates(states) => write!(f, "{} states is too many", states),
+            RuleFileError::InvalidLine(line, reason) => write!(f, "line {}: {}", line, reason),
+        }
+    }
+}
+
+impl std::error::Error for RuleFileError {}
+
+#[cfg(feature = "wasm")]
+impl From<RuleFileError> for JsValue {
+    fn from(error: RuleFileError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Neighborhood {
+    Moore,
+    VonNeumann,
+}
+
+impl Neighborhood {
+    // Positions in the Moore neighbourhood order N, NE, E, SE, S, SW, W, NW
+    // that @TABLE transitions list, after the centre cell.
+    fn table_order(&self) -> &'static [usize] {
+        match self {
+            Neighborhood::Moore => &[0, 1, 2, 3, 4, 5, 6, 7],
+            Neighborhood::VonNeumann => &[0, 2, 4, 6],
+        }
+    }
+
+    // Positions in the order @TREE levels consume them, before the centre.
+    fn tree_order(&self) -> &'static [usize] {
+        match self {
+            Neighborhood::Moore => &[7, 1, 5, 3, 0, 6, 2, 4],
+            Neighborhood::VonNeumann => &[0, 6, 2, 4],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Transitions {
+    Table { table: Vec<u8>, permute: bool },
+    Tree { nodes: Vec<Vec<u32>>, root: usize },
+}
+
+/// A Golly `.rule` file compiled for stepping. `@TABLE` sections are expanded
+/// into a dense lookup table, `@TREE` sections are walked directly. `@ICONS`
+/// is skipped, any other section is an error.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GollyRule {
+    source: String,
+    name: String,
+    states: usize,
+    neighborhood: Neighborhood,
+    transitions: Transitions,
+    colours: Vec<[u8; 3]>,
+}
+
+impl GollyRule {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The rule file this was parsed from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn states(&self) -> usize {
+        self.states
+    }
+
+    /// Colours from `@COLORS` indexed by state, empty if the file had none.
+    pub fn colours(&self) -> &[[u8; 3]] {
+        &self.colours
+    }
+
+    /// Next state of a cell given its Moore neighbours in the order N, NE, E,
+    /// SE, S, SW, W, NW. States past the rule's last one count as the last.
+    pub fn next_state(&self, center: u8, neighbors: &[u8; 8]) -> u8 {
+        let last = (self.states - 1) as u8;
+        let center = center.min(last);
+        match &self.transitions {
+            Transitions::Table { table, permute } => {
+                let mut inputs = [0u8; 8];
+                let order = self.neighborhood.table_order();
+                for (i, position) in order.iter().enumerate() {
+                    inputs[i] = neighbors[*position].min(last);
+                }
+                if *permute {
+                    inputs[..order.len()].sort_unstable();
+                }
+                let key = inputs[..order.len()]
+                    .iter()
+                    .rev()
+                    .fold(0, |key, state| key * self.states + *state as usize)
+                    * self.states
+                    + center as usize;
+                match table[key] {
+                    UNCHANGED => center,
+                    next => next,
+                }
+            }
+            Transitions::Tree { nodes, root } => {
+                let mut node = *root;
+                for position in self.neighborhood.tree_order() {
+                    node = nodes[node][neighbors[*position].min(last) as usize] as usize;
+                }
+                nodes[node][center as usize] as u8
+            }
+        }
+    }
+}
+
+impl FromStr for GollyRule {
+    type Err = RuleFileError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut name = None;
+        let mut sections: Vec<(String, Vec<(usize, &str)>)> = vec![];
+        for (index, line) in s.lines().enumerate() {
+            let trimmed = line.trim();
+            if let Some(header) = trimmed.strip_prefix('@') {
+                let mut words = header.split_whitespace();
+                let section = words.next().unwrap_or("").to_uppercase();
+                if section == "RULE" {
+                    name = words.next().map(|word| word.to_string());
+                }
+                sections.push((section, vec![]));
+            } else if let Some((_, lines)) = sections.last_mut() {
+                lines.push((index + 1, line));
+            }
+        }
+
+        let name = name.ok_or(RuleFileError::MissingRule)?;
+        let mut transitions = None;
+        let mut colour_lines = None;
+        for (section, lines) in &sections {
+            match section.as_str() {
+                "RULE" | "ICONS" => {}
+                "TABLE" => transitions = Some(parse_table(lines)?),
+                "TREE" => transitions = Some(parse_tree(lines)?),
+                "COLORS" => colour_lines = Some(lines),
+                _ => return Err(RuleFileError::UnsupportedSection(format!("@{}", section))),
+            }
+        }
+
+        let (states, neighborhood, transitions) =
+            transitions.ok_or(RuleFileError::MissingTransitions)?;
+        let colours = match colour_lines {
+            Some(lines) => parse_colours(lines, states)?,
+            None => vec![],
+        };
+
+        Ok(GollyRule {
+            source: s.to_string(),
+            name,
+            states,
+            neighborhood,
+            transitions,
+            colours,
+        })
+    }
+}
+
+fn content(line: &str) -> &str {
+    line.split('#').next().unwrap_or("").trim()
+}
+
+fn parse_states(value: &str, index: usize) -> Result<usize, RuleFileError> {
+    let states: usize = value
+        .trim()
+        .parse()
+        .map_err(|_| RuleFileError::InvalidLine(index, "invalid number of states".into()))?;
+    if !(2..UNCHANGED as usize).contains(&states) {
+        return Err(RuleFileError::TooManyStates(states));
+    }
+    Ok(states)
+}
+
+fn parse_neighborhood(value: &str) -> Result<Neighborhood, RuleFileError> {
+    match value.trim() {
+        "Moore" => Ok(Neighborhood::Moore),
+        "vonNeumann" => Ok(Neighborhood::VonNeumann),
+        other => Err(RuleFileError::UnsupportedNeighborhood(other.to_string())),
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Item {
+    State(u8),
+    Variable(usize),
+}
+
+fn parse_table(
+    lines: &[(usize, &str)],
+) -> Result<(usize, Neighborhood, Transitions), RuleFileError> {
+    let mut states = None;
+    let mut neighborhood = Neighborhood::Moore;
+    let mut symmetries = "none".to_string();
+    let mut variables: Vec<Vec<u8>> = vec![];
+    let mut names: HashMap<String, usize> = HashMap::new();
+    let mut rules: Vec<(usize, Vec<Item>)> = vec![];
+
+    for (index, line) in lines {
+        let line = content(line);
+        let invalid = |reason: &str| RuleFileError::InvalidLine(*index, reason.to_string());
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(value) = line.strip_prefix("n_states:") {
+            states = Some(parse_states(value, *index)?);
+        } else if let Some(value) = line.strip_prefix("neighborhood:") {
+            neighborhood = parse_neighborhood(value)?;
+        } else if let Some(value) = line.strip_prefix("symmetries:") {
+            symmetries = value.trim().to_string();
+        } else if let Some(declaration) = line.strip_prefix("var ") {
+            let (name, values) = declaration
+                .split_once('=')
+                .ok_or_else(|| invalid("expected var name={...}"))?;
+            let values = values
+                .trim()
+                .strip_prefix('{')
+                .and_then(|values| values.strip_suffix('}'))
+                .ok_or_else(|| invalid("variable values must be in braces"))?;
+            let mut expanded = vec![];
+            for value in values.split(',').map(str::trim) {
+                match (value.parse::<u8>(), names.get(value)) {
+                    (Ok(state), _) => expanded.push(state),
+                    (_, Some(variable)) => expanded.extend(variables[*variable].iter()),
+                    _ => return Err(invalid(&format!("unknown value {}", value))),
+                }
+            }
+            expanded.sort_unstable();
+            expanded.dedup();
+            names.insert(name.trim().to_string(), variables.len());
+            variables.push(expanded);
+        } else {
+            let tokens: Vec<String> = if line.contains(',') {
+                line.split(',')
+                    .map(|token| token.trim().to_string())
+                    .collect()
+            } else {
+                line.chars().map(|token| token.to_string()).collect()
+            };
+            let items = tokens
+                .iter()
+                .map(|token| match (token.parse::<u8>(), names.get(token)) {
+                    (Ok(state), _) => Ok(Item::State(state)),
+                    (_, Some(variable)) => Ok(Item::Variable(*variable)),
+                    _ => Err(invalid(&format!("unknown value {}", token))),
+                })
+                .collect::<Result<Vec<Item>, _>>()?;
+            rules.push((*index, items));
+        }
+    }
+
+    let states = states.ok_or(RuleFileError::InvalidLine(0, "missing n_states".into()))?;
+    let inputs = neighborhood.table_order().len() + 1;
+    let size = states
+        .checked_pow(inputs as u32)
+        .filter(|size| *size <= MAX_TABLE_SIZE)
+        .ok_or(RuleFileError::TooManyStates(states))?;
+    let (permutations, permute) = symmetry_permutations(&symmetries, neighborhood)?;
+
+    let mut table = vec![UNCHANGED; size];
+    for (index, items) in rules {
+        if items.len() != inputs + 1 {
+            return Err(RuleFileError::InvalidLine(
+                index,
+                format!("expected {} values", inputs + 1),
+            ));
+        }
+        let in_range = |item: &Item| match item {
+            Item::State(state) => (*state as usize) < states,
+            Item::Variable(variable) => variables[*variable]
+                .iter()
+                .all(|state| (*state as usize) < states),
+        };
+        if !items.iter().all(in_range) {
+            return Err(RuleFileError::InvalidLine(
+                index,
+                "state out of range".into(),
+            ));
+        }
+        if let Item::Variable(output) = items[inputs] {
+            if !items[..inputs].contains(&Item::Variable(output)) {
+                return Err(RuleFileError::InvalidLine(
+                    index,
+                    "output variable is not bound".into(),
+                ));
+            }
+        }
+
+        let mut bound: Vec<usize> = items[..inputs]
+            .iter()
+            .filter_map(|item| match item {
+                Item::Variable(variable) => Some(*variable),
+                Item::State(_) => None,
+            })
+            .collect();
+        bound.sort_unstable();
+        bound.dedup();
+        let combinations = bound
+            .iter()
+            .try_fold(1usize, |total, variable| {
+                total.checked_mul(variables[*variable].len())
+            })
+            .filter(|total| *total <= MAX_TABLE_SIZE)
+            .ok_or_else(|| RuleFileError::InvalidLine(index, "transition is too general".into()))?;
+
+        let mut choice = vec![0usize; bound.len()];
+        for _ in 0..combinations {
+            let value = |item: &Item| match item {
+                Item::State(state) => *state,
+                Item::Variable(variable) => {
+                    let slot = bound.iter().position(|bound| bound == variable).unwrap();
+                    variables[*variable][choice[slot]]
+                }
+            };
+            let concrete: Vec<u8> = items[..inputs].iter().map(value).collect();
+            let output = match items[inputs] {
+                Item::State(state) => state,
+                Item::Variable(variable) => {
+                    let slot = bound.iter().position(|bound| *bound == variable).unwrap();
+                    variables[variable][choice[slot]]
+                }
+            };
+
+            for permutation in &permutations {
+                let mut neighbors: Vec<u8> = permutation.iter().map(|i| concrete[1 + i]).collect();
+                if permute {
+                    neighbors.sort_unstable();
+                }
+                let key = neighbors
+                    .iter()
+                    .rev()
+                    .fold(0, |key, state| key * states + *state as usize)
+                    * states
+                    + concrete[0] as usize;
+                if table[key] == UNCHANGED {
+                    table[key] = output;
+                }
+            }
+
+            for (slot, variable) in bound.iter().enumerate() {
+                choice[slot] += 1;
+                if choice[slot] < variables[*variable].len() {
+                    break;
+                }
+                choice[slot] = 0;
+            }
+        }
+    }
+
+    Ok((states, neighborhood, Transitions::Table { table, permute }))
+}
+
+// Neighbour orderings equivalent under the symmetry, plus whether lookups
+// should sort the neighbours instead (`permute`).
+fn symmetry_permutations(
+    symmetries: &str,
+    neighborhood: Neighborhood,
+) -> Result<(Vec<Vec<usize>>, bool), RuleFileError> {
+    let count = neighborhood.table_order().len();
+    let rotate = |step: usize| -> Vec<usize> { (0..count).map(|i| (i + step) % count).collect() };
+    let reflect = |permutation: &Vec<usize>| -> Vec<usize> {
+        (0..count)
+            .map(|i| permutation[(count - i) % count])
+            .collect()
+    };
+    // Rotating by 90 degrees moves two places around the Moore ring but only
+    // one around the von Neumann one.
+    let quarter = count / 4;
+
+    let rotations = match symmetries {
+        "none" | "permute" | "reflect_horizontal" => vec![rotate(0)],
+        "rotate4" | "rotate4reflect" => (0..4).map(|turn| rotate(turn * quarter)).collect(),
+        "rotate8" | "rotate8reflect" if neighborhood == Neighborhood::Moore => {
+            (0..8).map(rotate).collect()
+        }
+        other => return Err(RuleFileError::UnsupportedSymmetry(other.to_string())),
+    };
+
+    let mut permutations = rotations.clone();
+    if symmetries.ends_with("reflect") || symmetries == "reflect_horizontal" {
+        permutations.extend(rotations.iter().map(reflect));
+    }
+    permutations.dedup();
+    Ok((permutations, symmetries == "permute"))
+}
+
+fn parse_tree(
+    lines: &[(usize, &str)],
+) -> Result<(usize, Neighborhood, Transitions), RuleFileError> {
+    let mut states = None;
+    let mut neighborhood = None;
+    let mut nodes: Vec<Vec<u32>> = vec![];
+    let mut levels: Vec<u32> = vec![];
+
+    for (index, line) in lines {
+        let line = content(line);
+        let invalid = |reason: &str| RuleFileError::InvalidLine(*index, reason.to_string());
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(value) = line.strip_prefix("num_states=") {
+            states = Some(parse_states(value, *index)?);
+        } else if let Some(value) = line.strip_prefix("num_neighbors=") {
+            neighborhood = Some(match value.trim() {
+                "8" => Neighborhood::Moore,
+                "4" => Neighborhood::VonNeumann,
+                other => return Err(RuleFileError::UnsupportedNeighborhood(other.to_string())),
+            });
+        } else if line.starts_with("num_nodes=") {
+            continue;
+        } else {
+            let values = line
+                .split_whitespace()
+                .map(|value| value.parse::<u32>())
+                .collect::<Result<Vec<u32>, _>>()
+                .map_err(|_| invalid("invalid tree node"))?;
+            let states = states.ok_or_else(|| invalid("num_states must come first"))?;
+            if values.len() != states + 1 {
+                return Err(invalid(&format!("expected {} values", states + 1)));
+            }
+            let level = values[0];
+            let children = values[1..].to_vec();
+            let valid = match level {
+                0 => false,
+                1 => children.iter().all(|state| (*state as usize) < states),
+                _ => children
+                    .iter()
+                    .all(|child| levels.get(*child as usize).copied() == Some(level - 1)),
+            };
+            if !valid {
+                return Err(invalid("tree node refers to an unknown node or state"));
+            }
+            nodes.push(children);
+            levels.push(level);
+        }
+    }
+
+    let states = states.ok_or(RuleFileError::InvalidLine(0, "missing num_states".into()))?;
+    let neighborhood = neighborhood.ok_or(RuleFileError::InvalidLine(
+        0,
+        "missing num_neighbors".into(),
+    ))?;
+    if nodes.is_empty() {
+        return Err(RuleFileError::MissingTransitions);
+    }
+    // The root consumes every neighbour and then the centre cell.
+    let root = nodes.len() - 1;
+    if levels[root] as usize != neighborhood.tree_order().len() + 1 {
+        return Err(RuleFileError::InvalidLine(
+            0,
+            format!(
+                "root node is not at level {}",
+                neighborhood.tree_order().len() + 1
+            ),
+        ));
+    }
+    Ok((states, neighborhood, Transitions::Tree { nodes, root }))
+}
+
+// Lines are either `state r g b` or a `r g b r g b` gradient for the live
+// states.
+fn parse_colours(lines: &[(usize, &str)], states: usize) -> Result<Vec<[u8; 3]>, RuleFileError> {
+    let mut colours = vec![[0, 0, 0]; states];
+    colours[0] = [48, 48, 48];
+    for (index, line) in lines {
+        let line = content(line);
+        if line.is_empty() {
+            continue;
+        }
+        let values = line
+            .split_whitespace()
+            .map(|value| value.parse::<u8>())
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| RuleFileError::InvalidLine(*index, "invalid colour".into()))?;
+        match values.as_slice() {
+            [state, r, g, b] if (*state as usize) < states => {
+                colours[*state as usize] = [*r, *g, *b];
+            }
+            [r1, g1, b1, r2, g2, b2] => {
+                let live = (states - 1).max(2) - 1;
+                for (state, colour) in colours.iter_mut().enumerate().skip(1) {
+                    let t = (state - 1) as f32 / live as f32;
+                    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+                    *colour = [mix(*r1, *r2), mix(*g1, *g2), mix(*b1, *b2)];
+                }
+            }
+            _ => return Err(RuleFileError::InvalidLine(*index, "invalid colour".into())),
+        }
+    }
+    Ok(colours)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIFE: &str = "@RULE Life
+
+@TABLE
+n_states:2
+neighborhood:Moore
+symmetries:permute
+var a={0,1}
+var b={a}
+var c={a}
+var d={a}
+var e={a}
+var f={a}
+var g={a}
+var h={a}
+var i={a}
+0,1,1,1,0,0,0,0,0,1
+1,1,1,0,0,0,0,0,0,1
+1,1,1,1,0,0,0,0,0,1
+a,b,c,d,e,f,g,h,i,0
+
+@COLORS
+0 0 0 0
+1 255 255 0
+";
+
+    fn neighbors(alive: &[usize]) -> [u8; 8] {
+        let mut neighbors = [0; 8];
+        alive.iter().for_each(|i| neighbors[*i] = 1);
+        neighbors
+    }
+
+    #[test]
+    fn parse_table_test() {
+        let rule: GollyRule = LIFE.parse().unwrap();
+        assert_eq!(rule.name(), "Life");
+        assert_eq!(rule.states(), 2);
+        assert_eq!(rule.next_state(0, &neighbors(&[1, 4, 6])), 1);
+        assert_eq!(rule.next_state(1, &neighbors(&[0, 7])), 1);
+        assert_eq!(rule.next_state(1, &neighbors(&[0])), 0);
+        assert_eq!(rule.next_state(0, &neighbors(&[0, 1, 2, 3])), 0);
+        assert_eq!(rule.next_state(5, &[5; 8]), 0);
+        assert_eq!(rule.colours(), &[[0, 0, 0], [255, 255, 0]]);
+    }
+
+    #[test]
+    fn rotate4_symmetry_test() {
+        let rule: GollyRule = "@RULE Arrow
+@TABLE
+n_states:2
+neighborhood:vonNeumann
+symmetries:rotate4
+0,1,0,0,0,1
+"
+        .parse()
+        .unwrap();
+        // A lone neighbour on any side causes a birth, two do not.
+        for side in [0, 2, 4, 6] {
+            assert_eq!(rule.next_state(0, &neighbors(&[side])), 1);
+        }
+        assert_eq!(rule.next_state(0, &neighbors(&[0, 4])), 0);
+        assert_eq!(rule.next_state(1, &neighbors(&[])), 1);
+    }
+
+    #[test]
+    fn parse_tree_test() {
+        // Every cell copies its northern neighbour.
+        let rule: GollyRule = "@RULE North
+@TREE
+num_states=2
+num_neighbors=4
+num_nodes=9
+1 0 0
+1 1 1
+2 0 0
+2 1 1
+3 2 2
+3 3 3
+4 4 4
+4 5 5
+5 6 7
+"
+        .parse()
+        .unwrap();
+        assert_eq!(rule.next_state(0, &neighbors(&[0])), 1);
+        assert_eq!(rule.next_state(1, &neighbors(&[2, 4, 6])), 0);
+        // Out of range states count as the last one.
+        assert_eq!(rule.next_state(9, &[7; 8]), 1);
+
+        let shallow = "@RULE X\n@TREE\nnum_states=2\nnum_neighbors=4\n1 0 0\n2 0 0\n";
+        assert_eq!(
+            shallow.parse::<GollyRule>(),
+            Err(RuleFileError::InvalidLine(
+                0,
+                "root node is not at level 5".into()
+            ))
+        );
+        let skipped = "@RULE X\n@TREE\nnum_states=2\nnum_neighbors=4\n1 0 0\n3 0 0\n";
+        assert_eq!(
+            skipped.parse::<GollyRule>(),
+            Err(RuleFileError::InvalidLine(
+                6,
+                "tree node refers to an unknown node or state".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn errors_test() {
+        assert_eq!(
+            "@TABLE\nn_states:2".parse::<GollyRule>(),
+            Err(RuleFileError::MissingRule)
+        );
+        assert_eq!(
+            "@RULE X\n@NAMES\n0 off".parse::<GollyRule>(),
+            Err(RuleFileError::UnsupportedSection("@NAMES".into()))
+        );
+        assert_eq!(
+            "@RULE X\n@TABLE\nn_states:2\nneighborhood:hexagonal".parse::<GollyRule>(),
+            Err(RuleFileError::UnsupportedNeighborhood("hexagonal".into()))
+        );
+        assert_eq!(
+            "@RULE X\n@TABLE\nn_states:2\nneighborhood:vonNeumann\nsymmetries:rotate8"
+                .parse::<GollyRule>(),
+            Err(RuleFileError::UnsupportedSymmetry("rotate8".into()))
+        );
+        assert_eq!(
+            "@RULE X\n@TABLE\nn_states:2\n0,1,0\n".parse::<GollyRule>(),
+            Err(RuleFileError::InvalidLine(4, "expected 10 values".into()))
+        );
+    }
+}
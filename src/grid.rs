@@ -1,27 +1,92 @@
 use std::convert::TryFrom;
 use std::fmt::{Display, Formatter};
+use std::sync::Arc;
 
 use rand::prelude::*;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
+#[cfg(feature = "image")]
+use crate::animation::Animation;
+use crate::backend::Backend;
+use crate::bit_grid::BitGrid;
 use crate::cell::Cell;
 use crate::cell_state::CellState;
+use crate::clipboard::{Clipboard, PasteMode, Transform};
+use crate::draw::{self, DrawMode};
+use crate::golly_rule::{GollyRule, RuleFileError};
+#[cfg(feature = "image")]
+use crate::image::{self, ImageError};
+use crate::life_rule::{LifeRule, ParseRuleError};
+use crate::macrocell::{Macrocell, MacrocellError};
+use crate::margolus::{MargolusPreset, MargolusRule, ParseMargolusError};
+use crate::noise::Noise;
 use crate::pattern::Pattern;
-use crate::pattern::Pattern::Pentadecathlon;
 use crate::random_bool;
+use crate::rle::{self, ParseRleError};
+use crate::seeded_rng::SeededRng;
+#[cfg(feature = "share")]
+use crate::share::{self, ShareError};
+use crate::snapshot::{Snapshot, SnapshotError};
+use crate::svg::SvgStyle;
+use crate::tiles::Tiles;
+use crate::update_schedule::UpdateSchedule;
+use crate::utils;
+use crate::variant::Variant;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GridError {
+    OutOfBounds(i64, i64),
+}
 
-#[wasm_bindgen]
-#[derive(Clone, Debug, Eq, PartialEq)]
+impl Display for GridError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GridError::OutOfBounds(x, y) => write!(f, "({}, {}) is outside the grid", x, y),
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+#[cfg(feature = "wasm")]
+impl From<GridError> for JsValue {
+    fn from(error: GridError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+/// Grids compare equal when their cells and settings match. The tiles kept
+/// for incremental stepping are a cache and are left out.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Grid {
     cells: Vec<Vec<Cell>>,
     size: (usize, usize),
+    variant: Variant,
+    rule: LifeRule,
+    brush: u8,
+    rng: SeededRng,
+    noise: Noise,
+    schedule: UpdateSchedule,
+    alpha: f64,
+    margolus: Option<MargolusRule>,
+    rule_file: Option<Arc<GollyRule>>,
+    generation: usize,
+    incremental: bool,
+    tiles: Tiles,
+    backend: Backend,
+    record_changes: bool,
+    born: Vec<u32>,
+    died: Vec<u32>,
+    selection: Option<(usize, usize, usize, usize)>,
+    clipboard: Option<Clipboard>,
 }
 
-const SURVIVE_VALUES: [usize; 4] = [1, 2, 3, 4];
-
-const BIRTH_VALUES: [usize; 2] = [3, 8];
+#[cfg(feature = "parallel")]
+const BAND_HEIGHT: usize = 16;
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl Grid {
     pub fn new(size_x: usize, size_y: usize) -> Self {
         let mut cells: Vec<Vec<Cell>> = vec![];
@@ -32,6 +97,7 @@ impl Grid {
                 let cell = Cell {
                     id: count,
                     state: CellState::Dead,
+                    colour: 0,
                 };
                 tmp.push(cell);
                 count += 1;
@@ -42,22 +108,255 @@ impl Grid {
         Self {
             cells,
             size: (size_x, size_y),
+            variant: Variant::Life,
+            rule: LifeRule::default(),
+            brush: 0,
+            rng: SeededRng::new(0),
+            noise: Noise::default(),
+            schedule: UpdateSchedule::Synchronous,
+            alpha: 1.0,
+            margolus: None,
+            rule_file: None,
+            generation: 0,
+            incremental: true,
+            tiles: Tiles::new((size_x, size_y)),
+            backend: Backend::Cells,
+            record_changes: false,
+            born: vec![],
+            died: vec![],
+            selection: None,
+            clipboard: None,
         }
     }
 
-    pub fn get_cells(&self) -> *const bool {
-        let mut flat: Vec<bool> = vec![];
+    pub fn get_colours(&self) -> Vec<u8> {
+        let mut flat: Vec<u8> = vec![];
         for y in 0..self.size.1 {
             for x in 0..self.size.0 {
-                let cell = self.cells[x][y];
-                match cell.state {
-                    CellState::Alive => flat.push(true),
-                    CellState::Dead => flat.push(false),
-                }
+                flat.push(self.cells[x][y].colour);
+            }
+        }
+
+        flat
+    }
+
+    pub fn width(&self) -> usize {
+        self.size.0
+    }
+
+    pub fn height(&self) -> usize {
+        self.size.1
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells
+            .iter()
+            .flatten()
+            .filter(|cell| cell.state == CellState::Alive)
+            .count()
+    }
+
+    pub fn colour_population(&self, colour: u8) -> usize {
+        self.cells
+            .iter()
+            .flatten()
+            .filter(|cell| cell.state == CellState::Alive && cell.colour == colour)
+            .count()
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+        self.tiles.invalidate();
+    }
+
+    /// The two state rule in `B3/S23` notation. Defaults to B38/S1234.
+    pub fn rule(&self) -> String {
+        self.rule.to_string()
+    }
+
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), ParseRuleError> {
+        self.set_life_rule(rule.parse()?);
+        Ok(())
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.rng.seed()
+    }
+
+    /// Restarts the random stream used by noisy stepping.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = SeededRng::new(seed);
+    }
+
+    pub fn set_transition_probability(&mut self, probability: f64) {
+        self.noise.transition_probability = probability.clamp(0.0, 1.0);
+    }
+
+    pub fn set_flip_rate(&mut self, rate: f64) {
+        self.noise.flip_rate = rate.clamp(0.0, 1.0);
+    }
+
+    pub fn suppressed_transitions(&self) -> usize {
+        self.noise.suppressed
+    }
+
+    pub fn flipped_cells(&self) -> usize {
+        self.noise.flipped
+    }
+
+    pub fn reset_noise_stats(&mut self) {
+        self.noise.suppressed = 0;
+        self.noise.flipped = 0;
+    }
+
+    pub fn schedule(&self) -> UpdateSchedule {
+        self.schedule
+    }
+
+    pub fn set_schedule(&mut self, schedule: UpdateSchedule) {
+        self.schedule = schedule;
+    }
+
+    /// Update probability used by `UpdateSchedule::AlphaAsynchronous`.
+    pub fn set_alpha(&mut self, alpha: f64) {
+        self.alpha = alpha.clamp(0.0, 1.0);
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn incremental(&self) -> bool {
+        self.incremental
+    }
+
+    /// Only recompute tiles near last generation's changes. Used by the
+    /// synchronous schedule without noise, which is the default.
+    pub fn set_incremental(&mut self, incremental: bool) {
+        self.incremental = incremental;
+        self.tiles.invalidate();
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    pub fn set_backend(&mut self, backend: Backend) {
+        self.backend = backend;
+    }
+
+    /// Keep the row-major indices of the cells born and died in each
+    /// `step_forward`, so a frontend can repaint only those.
+    pub fn set_record_changes(&mut self, record: bool) {
+        self.record_changes = record;
+        self.born.clear();
+        self.died.clear();
+    }
+
+    pub fn born_count(&self) -> usize {
+        self.born.len()
+    }
+
+    pub fn died_count(&self) -> usize {
+        self.died.len()
+    }
+
+    /// Number of tiles that changed in the last incremental step.
+    pub fn active_tiles(&self) -> usize {
+        self.tiles.active()
+    }
+
+    /// Switches the grid to a Margolus block rule such as
+    /// `MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15`. Blocks start at even
+    /// coordinates on even generations and odd ones on odd generations.
+    pub fn set_margolus_rule(&mut self, rule: &str) -> Result<(), ParseMargolusError> {
+        self.margolus = Some(rule.parse()?);
+        self.tiles.invalidate();
+        Ok(())
+    }
+
+    pub fn set_margolus_preset(&mut self, preset: MargolusPreset) {
+        self.margolus = preset.rule().parse().ok();
+        self.tiles.invalidate();
+    }
+
+    pub fn clear_margolus_rule(&mut self) {
+        self.margolus = None;
+        self.tiles.invalidate();
+    }
+
+    pub fn margolus_rule(&self) -> Option<String> {
+        self.margolus.map(|rule| rule.to_string())
+    }
+
+    pub fn is_reversible(&self) -> bool {
+        self.margolus.is_some_and(|rule| rule.is_reversible())
+    }
+
+    /// Undoes the last generation. Only possible with a reversible Margolus
+    /// rule and not before generation 0.
+    pub fn step_back(&mut self) -> bool {
+        let inverse = match self.margolus.and_then(|rule| rule.inverse().copied()) {
+            Some(inverse) => inverse,
+            None => return false,
+        };
+        if self.generation == 0 {
+            return false;
+        }
+
+        self.generation -= 1;
+        self.step_blocks(&inverse, self.generation % 2);
+        self.tiles.invalidate();
+        true
+    }
+
+    /// Steps the grid with a Golly `.rule` file. State 0 is a dead cell and
+    /// state n is a live cell with colour n - 1. Cells in states the rule
+    /// doesn't have are moved to its last state.
+    pub fn load_rule_file(&mut self, text: &str) -> Result<(), RuleFileError> {
+        self.rule_file = Some(Arc::new(text.parse()?));
+        for x in 0..self.size.0 {
+            for y in 0..self.size.1 {
+                let state = Self::state_of(self.cells[x][y]);
+                self.cells[x][y] = Self::with_state(self.cells[x][y], self.clamp_state(state));
             }
         }
+        self.tiles.invalidate();
+        Ok(())
+    }
+
+    pub fn clear_rule_file(&mut self) {
+        self.rule_file = None;
+        self.tiles.invalidate();
+    }
+
+    pub fn rule_file_name(&self) -> Option<String> {
+        self.rule_file.as_ref().map(|rule| rule.name().to_string())
+    }
+
+    pub fn rule_file_states(&self) -> usize {
+        self.rule_file.as_ref().map_or(2, |rule| rule.states())
+    }
 
-        flat.as_ptr()
+    pub fn get_state(&self, x: usize, y: usize) -> u8 {
+        if x < self.size.0 && y < self.size.1 {
+            Self::state_of(self.cells[x][y])
+        } else {
+            0
+        }
+    }
+
+    /// Sets a cell to `state`, or the last state of a loaded rule file if
+    /// that has fewer.
+    pub fn set_state(&mut self, x: usize, y: usize, state: u8) {
+        if x < self.size.0 && y < self.size.1 {
+            self.cells[x][y] = Self::with_state(self.cells[x][y], self.clamp_state(state));
+            self.tiles.invalidate();
+        }
     }
 
     pub fn render(&self) -> String {
@@ -73,8 +372,9 @@ impl Grid {
         // self.spawn_pulsar(60, 35);
     }
 
-    pub fn spawn_pattern(&mut self, pattern: Pattern, x: usize, y: usize) {
-        if x >= 0 && y >= 0 && x < self.size.0 && y < self.size.1 {
+    pub fn spawn_pattern(&mut self, pattern: Pattern, x: usize, y: usize, colour: u8) {
+        self.brush = colour;
+        if x < self.size.0 && y < self.size.1 {
             match pattern {
                 Pattern::Blinker => self.spawn_blinker(x, y),
                 Pattern::Toad => self.spawn_toad(x, y),
@@ -106,6 +406,7 @@ impl Grid {
                 Pattern::InfiniteGrowth4 => self.spawn_infinite_growth_4(x, y),
             }
         }
+        self.brush = 0;
     }
 
     pub fn randomize(&mut self) {
@@ -150,7 +451,7 @@ impl Grid {
                 if random_bool(&mut rng) {
                     let rand = rng.gen_range(0..Pattern::count());
                     let patterns = Pattern::get_patterns();
-                    self.spawn_pattern(patterns[rand], x, y);
+                    self.spawn_pattern(patterns[rand], x, y, 0);
                 }
             }
         }
@@ -159,55 +460,398 @@ impl Grid {
     pub fn reset(&mut self) {
         for x in 0..self.size.0 {
             for y in 0..self.size.1 {
-                self.update_cell(x, y, CellState::Dead);
+                self.kill_cell(x, y);
             }
         }
     }
 
-    pub fn rle_spawn(&mut self, x: usize, y: usize, rle: &str) {
-        let rules: Vec<&str> = rle.split("").collect();
-        let mut cur_x = x;
-        let mut cur_y = y;
-        let mut count = String::new();
-        rules.iter().for_each(|rule| match *rule {
-            "b" => {
-                if count.is_empty() {
-                    cur_x += 1;
-                } else {
-                    cur_x += count.parse::<usize>().unwrap();
-                    count = String::new();
+    /// Spawns an RLE pattern with its top left corner at (`x`, `y`). Cells
+    /// past the edges are dropped, but the pattern itself must fit the grid.
+    pub fn rle_spawn(
+        &mut self,
+        x: usize,
+        y: usize,
+        rle: &str,
+        colour: u8,
+    ) -> Result<(), ParseRleError> {
+        let cells = rle::parse_rle(rle, self.size)?
+            .into_iter()
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .collect();
+        self.revive_cells(cells, colour);
+        Ok(())
+    }
+
+    /// The live cells as an RLE pattern cropped to their bounding box.
+    pub fn to_rle(&self) -> String {
+        rle::write_rle(&self.live_cells(), &self.rule.to_string())
+    }
+
+    /// Selects the `width` x `height` rectangle from (`x`, `y`), clipped to
+    /// the grid. An empty rectangle clears the selection.
+    pub fn select(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        let region = draw::clip_region((x, y, width, height), self.size.0, self.size.1);
+        self.selection = Some(region).filter(|region| region.2 > 0 && region.3 > 0);
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+    }
+
+    /// Copies the selected cells, dead ones included, into the clipboard.
+    /// Returns false when nothing is selected.
+    pub fn copy_selection(&mut self) -> bool {
+        let (left, top, width, height) = match self.selection {
+            Some(selection) => selection,
+            None => return false,
+        };
+        let mut states = Vec::with_capacity(width * height);
+        for y in top..top + height {
+            for x in left..left + width {
+                states.push(Self::state_of(self.cells[x][y]));
+            }
+        }
+        self.clipboard = Clipboard::new(width, height, states).ok();
+        self.clipboard.is_some()
+    }
+
+    /// Copies the selection and then kills every cell in it.
+    pub fn cut_selection(&mut self) -> bool {
+        if !self.copy_selection() {
+            return false;
+        }
+        if let Some((left, top, width, height)) = self.selection {
+            for x in left..left + width {
+                for y in top..top + height {
+                    self.cells[x][y] = Self::with_state(self.cells[x][y], 0);
                 }
             }
-            "o" => {
-                if count.is_empty() {
-                    self.revive_cell(cur_x, cur_y);
-                    cur_x += 1;
-                } else {
-                    let c: usize = count.parse().unwrap();
-                    self.revive_cells_in_row(c, cur_x, cur_y);
-                    cur_x += c;
-                    count = String::new();
+            self.tiles.invalidate();
+        }
+        true
+    }
+
+    pub fn has_clipboard(&self) -> bool {
+        self.clipboard.is_some()
+    }
+
+    /// The clipboard's live cells as RLE with the grid's rule, cropped to
+    /// their bounding box. Colours are not kept.
+    pub fn clipboard_to_rle(&self) -> Option<String> {
+        self.clipboard
+            .as_ref()
+            .map(|clipboard| rle::write_rle(&clipboard.live_cells(), &self.rule.to_string()))
+    }
+
+    /// Replaces the clipboard with an RLE pattern as colour 0 cells.
+    pub fn set_clipboard_rle(&mut self, rle: &str) -> Result<(), ParseRleError> {
+        self.clipboard = Some(Clipboard::from_cells(&rle::parse_rle(rle, self.size)?));
+        Ok(())
+    }
+
+    /// Pastes the clipboard with its top left cell at (`x`, `y`) after
+    /// applying `transform`. Cells falling off the grid are dropped. Returns
+    /// false when the clipboard is empty.
+    pub fn paste(&mut self, x: usize, y: usize, transform: Transform, mode: PasteMode) -> bool {
+        let clipboard = match &self.clipboard {
+            Some(clipboard) => clipboard.transformed(transform),
+            None => return false,
+        };
+        let (_, _, width, height) = draw::clip_region(
+            (x, y, clipboard.width(), clipboard.height()),
+            self.size.0,
+            self.size.1,
+        );
+        for dx in 0..width {
+            for dy in 0..height {
+                let cell = self.cells[x + dx][y + dy];
+                let state = mode.apply(Self::state_of(cell), clipboard.state(dx, dy));
+                self.cells[x + dx][y + dy] = Self::with_state(cell, self.clamp_state(state));
+            }
+        }
+        self.tiles.invalidate();
+        true
+    }
+
+    /// Draws a line from (`x0`, `y0`) to (`x1`, `y1`) in `state`, 0 for dead
+    /// and colour + 1 for live cells, clamped like `set_state`. Cells off the
+    /// grid are skipped. Returns the number of cells changed.
+    pub fn draw_line(
+        &mut self,
+        x0: usize,
+        y0: usize,
+        x1: usize,
+        y1: usize,
+        mode: DrawMode,
+        state: u8,
+    ) -> usize {
+        let points = draw::line((x0, y0), (x1, y1), self.size);
+        self.draw(&points, mode, state)
+    }
+
+    /// Draws the outline of the `width` x `height` box from (`x`, `y`).
+    pub fn draw_rectangle(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        mode: DrawMode,
+        state: u8,
+    ) -> usize {
+        let points = draw::rectangle((x, y), (width, height), false, self.size);
+        self.draw(&points, mode, state)
+    }
+
+    pub fn fill_rectangle(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        mode: DrawMode,
+        state: u8,
+    ) -> usize {
+        let points = draw::rectangle((x, y), (width, height), true, self.size);
+        self.draw(&points, mode, state)
+    }
+
+    /// Draws the outline of the ellipse that fits the `width` x `height`
+    /// box from (`x`, `y`).
+    pub fn draw_ellipse(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        mode: DrawMode,
+        state: u8,
+    ) -> usize {
+        let points = draw::ellipse((x, y), (width, height), false, self.size);
+        self.draw(&points, mode, state)
+    }
+
+    pub fn fill_ellipse(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        mode: DrawMode,
+        state: u8,
+    ) -> usize {
+        let points = draw::ellipse((x, y), (width, height), true, self.size);
+        self.draw(&points, mode, state)
+    }
+
+    /// Sets every cell side-connected to (`x`, `y`) that shares its state to
+    /// `state`, so a dead pocket or a live wall can be filled alike. Returns
+    /// the number of cells changed.
+    pub fn flood_fill(&mut self, x: usize, y: usize, state: u8) -> usize {
+        if x >= self.size.0 || y >= self.size.1 {
+            return 0;
+        }
+        let state = self.clamp_state(state);
+        let target = Self::state_of(self.cells[x][y]);
+        if target == state {
+            return 0;
+        }
+        let mut points = vec![];
+        let mut stack = vec![(x, y)];
+        let mut seen = vec![false; self.size.0 * self.size.1];
+        seen[y * self.size.0 + x] = true;
+        while let Some((x, y)) = stack.pop() {
+            points.push((x, y));
+            let neighbours = [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ];
+            for (nx, ny) in neighbours.iter().copied() {
+                if nx < self.size.0
+                    && ny < self.size.1
+                    && !seen[ny * self.size.0 + nx]
+                    && Self::state_of(self.cells[nx][ny]) == target
+                {
+                    seen[ny * self.size.0 + nx] = true;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+        self.draw(&points, DrawMode::Set, state)
+    }
+
+    /// Kills the live cells in the rectangle and sets the dead ones to
+    /// `state`.
+    pub fn invert_region(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        state: u8,
+    ) -> usize {
+        self.fill_rectangle(x, y, width, height, DrawMode::Toggle, state)
+    }
+
+    /// PNG bytes with every cell drawn as a `cell_px` square. `colours` are
+    /// flat RGB triples for state 0 (dead) and then each live colour, like
+    /// `rule_file_colours`. Empty uses the demo's palette.
+    #[cfg(feature = "image")]
+    pub fn to_png(&self, cell_px: usize, colours: &[u8]) -> Result<Vec<u8>, ImageError> {
+        let palette = utils::palette(colours);
+        let cell_px = cell_px.max(1);
+        let (width, height) = (self.size.0 * cell_px, self.size.1 * cell_px);
+        let mut rgb = vec![0; width * height * 3];
+        for (x, column) in self.cells.iter().enumerate() {
+            for (y, cell) in column.iter().enumerate() {
+                let colour = palette[Self::state_of(*cell) as usize % palette.len()];
+                for py in y * cell_px..(y + 1) * cell_px {
+                    for px in x * cell_px..(x + 1) * cell_px {
+                        let i = (py * width + px) * 3;
+                        rgb[i..i + 3].copy_from_slice(&colour);
+                    }
+                }
+            }
+        }
+        image::encode_png(width, height, &rgb)
+    }
+
+    /// Steps `generations` times and returns the run as an animated GIF or
+    /// APNG, starting with the current generation.
+    #[cfg(feature = "image")]
+    pub fn record_animation(
+        &mut self,
+        generations: usize,
+        animation: &Animation,
+    ) -> Result<Vec<u8>, ImageError> {
+        let (left, top, width, height) = animation.region(self.size.0, self.size.1);
+        let mut frames = Vec::with_capacity(generations + 1);
+        for generation in 0..=generations {
+            if generation > 0 {
+                self.step_forward();
+            }
+            let mut states = Vec::with_capacity(width * height);
+            for y in top..top + height {
+                for x in left..left + width {
+                    states.push(Self::state_of(self.cells[x][y]));
                 }
             }
-            "$" => {
-                cur_y += 1;
-                cur_x = x;
+            frames.push(states);
+        }
+        animation.encode(width, height, &frames)
+    }
+
+    /// The `width` x `height` cells from (`x`, `y`) as an SVG, with each
+    /// horizontal run of same coloured cells drawn as one rectangle. With
+    /// several phases the following generations are drawn alongside; the
+    /// grid itself is not stepped.
+    pub fn region_to_svg(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        style: &SvgStyle,
+    ) -> String {
+        let (left, top, width, height) =
+            draw::clip_region((x, y, width, height), self.size.0, self.size.1);
+        let mut grid = self.clone();
+        let mut phases = Vec::with_capacity(style.phases());
+        for phase in 0..style.phases() {
+            if phase > 0 {
+                grid.step_forward();
             }
-            "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" | "0" => {
-                count.push_str(rule);
+            let mut states = Vec::with_capacity(width * height);
+            for y in top..top + height {
+                for x in left..left + width {
+                    states.push(Self::state_of(grid.cells[x][y]));
+                }
             }
-            _ => {}
-        });
+            phases.push(states);
+        }
+        style.render((left, top), width, height, &phases)
+    }
+
+    /// The whole grid in the versioned binary snapshot format: size, rules,
+    /// settings, generation, random stream and cells.
+    pub fn save_snapshot(&self) -> Vec<u8> {
+        self.snapshot().encode()
+    }
+
+    pub fn load_snapshot(bytes: &[u8]) -> Result<Grid, SnapshotError> {
+        Grid::from_snapshot(Snapshot::decode(bytes)?)
+    }
+
+    /// A URL-safe string that `from_share_string` turns back into this grid:
+    /// the snapshot, deflated and base64url encoded. Fails with
+    /// `ShareError::TooLarge` past `MAX_SHARE_LENGTH` characters.
+    #[cfg(feature = "share")]
+    pub fn to_share_string(&self) -> Result<String, ShareError> {
+        share::encode(&self.save_snapshot())
+    }
+
+    /// Fails like `load_snapshot` for grids over `MAX_CELLS` cells.
+    #[cfg(feature = "share")]
+    pub fn from_share_string(text: &str) -> Result<Grid, ShareError> {
+        Ok(Grid::load_snapshot(&share::decode(text)?)?)
+    }
+
+    /// Replaces the grid with a Golly Macrocell pattern, centred. Patterns
+    /// bigger than the grid are cropped to their middle.
+    pub fn load_macrocell(&mut self, text: &str) -> Result<(), MacrocellError> {
+        let pattern: Macrocell = text.parse()?;
+        let (left, top) = match pattern.bounds() {
+            Some((left, top, width, height)) => (
+                left + (width as i64 - self.size.0 as i64) / 2,
+                top + (height as i64 - self.size.1 as i64) / 2,
+            ),
+            None => (0, 0),
+        };
+        self.show_macrocell(&pattern, left, top)
+    }
+
+    /// The grid as a Golly Macrocell pattern with its rule and generation.
+    /// The `#R` line is left out while a Margolus rule or rule file is
+    /// active, since it can only hold the B/S rule.
+    pub fn to_macrocell(&self) -> String {
+        let cells: Vec<_> = self
+            .cells()
+            .map(|(x, y, cell)| (x, y, Self::state_of(cell)))
+            .collect();
+        let rule =
+            Some(self.rule()).filter(|_| self.margolus.is_none() && self.rule_file.is_none());
+        Macrocell::from_cells(&cells, rule, self.generation as u64).to_string()
     }
 
-    fn revive_cells_in_row(&mut self, count: usize, x: usize, y: usize) {
-        for i in 0..count {
-            self.revive_cell(x + i, y);
+    /// Replaces the grid with a PNG, one pixel per cell from the top left.
+    /// Pixels with a luminance (0 to 1) of at least `threshold` become live
+    /// cells; pass a negative threshold to invert, so dark pixels live.
+    #[cfg(feature = "image")]
+    pub fn load_image(&mut self, bytes: &[u8], threshold: f64) -> Result<(), ImageError> {
+        let (width, height, luminance) = image::decode_luminance(bytes)?;
+        self.reset();
+        let mut alive = vec![];
+        for y in 0..height.min(self.size.1) {
+            for x in 0..width.min(self.size.0) {
+                let light = luminance[y * width + x];
+                let lit = if threshold < 0.0 {
+                    light <= -threshold
+                } else {
+                    light >= threshold
+                };
+                if lit {
+                    alive.push((x, y));
+                }
+            }
         }
+        self.revive_cells(alive, 0);
+        Ok(())
     }
 
     pub fn spawn_glider_1(&mut self, x: usize, y: usize) {
-        if x - 1 >= 0 && x + 1 < self.size.0 && y - 1 >= 0 && y + 1 < self.size.1 {
+        if x >= 1 && x + 1 < self.size.0 && y >= 1 && y + 1 < self.size.1 {
             self.set_cells_alive(vec![
                 (x, y - 1),
                 (x + 1, y),
@@ -219,7 +863,7 @@ impl Grid {
     }
 
     pub fn spawn_glider_2(&mut self, x: usize, y: usize) {
-        if x - 1 >= 0 && x + 1 < self.size.0 && y - 1 >= 0 && y + 1 < self.size.1 {
+        if x >= 1 && x + 1 < self.size.0 && y >= 1 && y + 1 < self.size.1 {
             self.set_cells_alive(vec![
                 (x, y + 1),
                 (x + 1, y),
@@ -231,7 +875,7 @@ impl Grid {
     }
 
     pub fn spawn_glider_3(&mut self, x: usize, y: usize) {
-        if x - 1 >= 0 && x + 1 < self.size.0 && y - 1 >= 0 && y + 1 < self.size.1 {
+        if x >= 1 && x + 1 < self.size.0 && y >= 1 && y + 1 < self.size.1 {
             self.set_cells_alive(vec![
                 (x, y - 1),
                 (x - 1, y),
@@ -243,7 +887,7 @@ impl Grid {
     }
 
     pub fn spawn_glider_4(&mut self, x: usize, y: usize) {
-        if x - 1 >= 0 && x + 1 < self.size.0 && y - 1 >= 0 && y + 1 < self.size.1 {
+        if x >= 1 && x + 1 < self.size.0 && y >= 1 && y + 1 < self.size.1 {
             self.set_cells_alive(vec![
                 (x, y + 1),
                 (x - 1, y),
@@ -255,13 +899,13 @@ impl Grid {
     }
 
     pub fn spawn_blinker(&mut self, x: usize, y: usize) {
-        if x - 1 >= 0 && x + 1 < self.size.0 && y - 1 >= 0 && y + 1 < self.size.1 {
+        if x >= 1 && x + 1 < self.size.0 && y >= 1 && y + 1 < self.size.1 {
             self.set_cells_alive(vec![(x, y - 1), (x, y), (x, y + 1)]);
         }
     }
 
     pub fn spawn_block(&mut self, x: usize, y: usize) {
-        if x >= 0 && y >= 0 && x + 1 < self.size.0 && y + 1 < self.size.1 {
+        if x + 1 < self.size.0 && y + 1 < self.size.1 {
             self.set_cells_alive(vec![(x, y), (x, y + 1), (x + 1, y), (x + 1, y + 1)]);
         }
     }
@@ -306,7 +950,7 @@ impl Grid {
     }
 
     pub fn spawn_toad(&mut self, x: usize, y: usize) {
-        if x - 1 >= 0 && y - 2 >= 0 && x + 2 < self.size.0 && y + 1 < self.size.1 {
+        if x >= 1 && y >= 2 && x + 2 < self.size.0 && y + 1 < self.size.1 {
             self.set_cells_alive(vec![
                 (x, y),
                 (x, y - 1),
@@ -319,7 +963,7 @@ impl Grid {
     }
 
     pub fn spawn_beacon(&mut self, x: usize, y: usize) {
-        if x - 1 >= 0 && y - 1 >= 0 && x + 2 < self.size.0 && y + 2 < self.size.1 {
+        if x >= 1 && y >= 1 && x + 2 < self.size.0 && y + 2 < self.size.1 {
             self.set_cells_alive(vec![
                 (x, y),
                 (x, y - 1),
@@ -334,7 +978,7 @@ impl Grid {
     }
 
     pub fn spawn_pulsar(&mut self, x: usize, y: usize) {
-        if x - 6 >= 0 && x + 6 < self.size.0 && y - 6 >= 0 && y + 6 < self.size.1 {
+        if x >= 6 && x + 6 < self.size.0 && y >= 6 && y + 6 < self.size.1 {
             self.set_cells_alive(vec![
                 //upper left
                 (x - 2, y - 1),
@@ -393,7 +1037,7 @@ impl Grid {
     }
 
     pub fn spawn_pentadecanthlon(&mut self, x: usize, y: usize) {
-        if x - 4 >= 0 && y - 7 >= 0 && x + 4 < self.size.0 && y + 8 < self.size.1 {
+        if x >= 4 && y >= 7 && x + 4 < self.size.0 && y + 8 < self.size.1 {
             self.set_cells_alive(vec![
                 (x, y),
                 (x, y - 1),
@@ -466,7 +1110,7 @@ impl Grid {
     }
 
     pub fn spawn_glider_gun(&mut self, x: usize, y: usize) {
-        if x - 18 >= 0 && y - 4 >= 0 && x + 17 < self.size.0 && y + 4 < self.size.1 {
+        if x >= 18 && y >= 4 && x + 17 < self.size.0 && y + 4 < self.size.1 {
             self.set_cells_alive(vec![
                 //left
                 (x - 1, y + 1),
@@ -756,7 +1400,7 @@ impl Grid {
     }
 
     pub fn spawn_glider_loop(&mut self, x: usize, y: usize) {
-        if x - 32 >= 0 && y - 32 >= 0 && x + 32 < self.size.0 && y + 32 < self.size.1 {
+        if x >= 32 && y >= 32 && x + 32 < self.size.0 && y + 32 < self.size.1 {
             self.set_cells_alive(vec![
                 (x - 5, y - 32),
                 (x - 4, y - 32),
@@ -1049,52 +1693,516 @@ impl Grid {
     }
 
     pub fn step_forward(&mut self) {
-        let state = self.clone();
-        for x in 0..self.size.0 {
-            for y in 0..self.size.1 {
-                let alive_neighbors = state.alive_neighbors(x, y);
-                let current_cell = state.get_cell(x, y);
-                let mut future_cell = self.get_mut_cell(x, y);
-                if current_cell.state == CellState::Alive
-                    && (!SURVIVE_VALUES.contains(&alive_neighbors))
-                {
-                    future_cell.state = CellState::Dead;
+        self.step();
+    }
+
+    // Steps once and tells whether any cell changed.
+    fn step(&mut self) -> bool {
+        if !self.record_changes {
+            return self.advance();
+        }
+
+        let before = self.alive_flags();
+        let changed = self.advance();
+        self.born.clear();
+        self.died.clear();
+        for (i, (was, is)) in before.into_iter().zip(self.alive_flags()).enumerate() {
+            match (was, is) {
+                (false, true) => self.born.push(i as u32),
+                (true, false) => self.died.push(i as u32),
+                _ => {}
+            }
+        }
+        changed
+    }
+
+    /// Runs up to `n` generations and returns how many were run. Stops early
+    /// once the grid is empty or a generation leaves it unchanged.
+    pub fn step_n(&mut self, n: usize) -> usize {
+        if self.batches_packed() {
+            return self.step_packed(|generations| generations < n);
+        }
+        for generation in 0..n {
+            if !self.step_unsettled() {
+                return generation + 1;
+            }
+        }
+        n
+    }
+
+    /// Runs generations until `budget_ms` milliseconds have passed and
+    /// returns how many were run. Stops early like `step_n`.
+    pub fn step_for_ms(&mut self, budget_ms: f64) -> usize {
+        let start = utils::now_ms();
+        if self.batches_packed() {
+            return self.step_packed(|_| utils::now_ms() - start < budget_ms);
+        }
+        let mut generations = 0;
+        while utils::now_ms() - start < budget_ms {
+            generations += 1;
+            if !self.step_unsettled() {
+                break;
+            }
+        }
+        generations
+    }
+
+    // Runs generations on the packed cells for as long as `more` allows and
+    // stops early like `step_n`. Nothing in between needs the cells, so they
+    // are only unpacked once at the end.
+    fn step_packed(&mut self, mut more: impl FnMut(usize) -> bool) -> usize {
+        let mut bits = self.pack();
+        let mut generations = 0;
+        while more(generations) {
+            let next = bits.step(self.rule.birth, self.rule.survive);
+            generations += 1;
+            let settled = next == bits || next.is_empty();
+            bits = next;
+            if settled {
+                break;
+            }
+        }
+
+        for (x, column) in self.cells.iter_mut().enumerate() {
+            for (y, cell) in column.iter_mut().enumerate() {
+                match (cell.state, bits.get(x, y)) {
+                    (CellState::Dead, true) => {
+                        cell.state = CellState::Alive;
+                        cell.colour = 0;
+                    }
+                    (CellState::Alive, false) => cell.state = CellState::Dead,
+                    _ => {}
                 }
-                if current_cell.state == CellState::Dead
-                    && (BIRTH_VALUES.contains(&alive_neighbors))
-                {
-                    future_cell.state = CellState::Alive;
+            }
+        }
+        self.tiles.invalidate();
+        self.generation += generations;
+        generations
+    }
+
+    // Steps once and tells whether the grid is still alive and changing.
+    fn step_unsettled(&mut self) -> bool {
+        self.step()
+            && self
+                .cells
+                .iter()
+                .flatten()
+                .any(|cell| cell.state == CellState::Alive)
+    }
+
+    // Row-major, like the arrays handed to JS.
+    fn alive_flags(&self) -> Vec<bool> {
+        (0..self.size.1)
+            .flat_map(|y| (0..self.size.0).map(move |x| (x, y)))
+            .map(|(x, y)| self.cells[x][y].state == CellState::Alive)
+            .collect()
+    }
+
+    // Tells whether any cell changed.
+    fn advance(&mut self) -> bool {
+        if let Some(rule) = self.margolus {
+            let changed = self.step_blocks(rule.table(), self.generation % 2);
+            self.tiles.invalidate();
+            self.generation += 1;
+            return changed;
+        }
+
+        let plain = self.schedule == UpdateSchedule::Synchronous
+            && self.noise.transition_probability >= 1.0
+            && self.noise.flip_rate == 0.0;
+        if self.steps_packed() {
+            let changed = self.step_bits();
+            self.tiles.invalidate();
+            self.generation += 1;
+            return changed;
+        }
+        if plain && self.incremental {
+            let changed = self.step_tiles();
+            self.generation += 1;
+            return changed;
+        }
+        #[cfg(feature = "parallel")]
+        if plain {
+            let changed = self.step_bands();
+            self.tiles.invalidate();
+            self.generation += 1;
+            return changed;
+        }
+
+        let mut changed = false;
+        match self.schedule {
+            UpdateSchedule::Synchronous => changed = self.step_synchronous(),
+            UpdateSchedule::RandomOrder | UpdateSchedule::Sweep => {
+                let mut order: Vec<(usize, usize)> = (0..self.size.1)
+                    .flat_map(|y| (0..self.size.0).map(move |x| (x, y)))
+                    .collect();
+                if self.schedule == UpdateSchedule::RandomOrder {
+                    order.shuffle(&mut self.rng);
+                }
+                for (x, y) in order {
+                    changed |= self.apply_transition(self.transition(x, y), x, y);
+                }
+            }
+            UpdateSchedule::AlphaAsynchronous => {
+                let mut transitions = self.transitions().into_iter().peekable();
+                for x in 0..self.size.0 {
+                    for y in 0..self.size.1 {
+                        let transition = transitions
+                            .next_if(|(tx, ty, _)| (*tx, *ty) == (x, y))
+                            .map(|(_, _, cell)| cell);
+                        if self.rng.gen_bool(self.alpha) {
+                            changed |= self.apply_transition(transition, x, y);
+                        }
+                    }
                 }
             }
         }
+
+        if self.noise.flip_rate > 0.0 {
+            changed |= self.flip_noise();
+        }
+        self.tiles.invalidate();
+        self.generation += 1;
+        changed
     }
 
-    fn kill_cell(&mut self, x: usize, y: usize) {
-        self.update_cell(x, y, CellState::Dead);
+    // Whether generations run on a `BitGrid`: a two state rule on the
+    // bit-parallel backend, stepped synchronously without noise.
+    fn steps_packed(&self) -> bool {
+        self.backend == Backend::BitParallel
+            && self.margolus.is_none()
+            && self.rule_file.is_none()
+            && self.schedule == UpdateSchedule::Synchronous
+            && self.noise.transition_probability >= 1.0
+            && self.noise.flip_rate == 0.0
     }
 
-    pub fn revive_cell(&mut self, x: usize, y: usize) {
-        self.update_cell(x, y, CellState::Alive);
+    // Whether several generations can run packed, with nothing reading the
+    // cells in between: no recorded changes and no birth colours.
+    fn batches_packed(&self) -> bool {
+        self.steps_packed() && !self.record_changes && self.variant.colours() == 1
     }
 
-    fn get_cell(&self, x: usize, y: usize) -> Cell {
-        self.cells[x][y]
+    fn pack(&self) -> BitGrid {
+        let mut bits = BitGrid::new(self.size.0, self.size.1);
+        for (x, y) in self.live_cells() {
+            bits.set(x, y, true);
+        }
+        bits
     }
 
-    fn update_cell(&mut self, x: usize, y: usize, state: CellState) {
-        if x < self.size.0 && y < self.size.1 {
-            self.cells[x][y].state = state;
+    fn step_synchronous(&mut self) -> bool {
+        let mut changed = false;
+        for (x, y, cell) in self.transitions() {
+            changed |= self.apply_transition(Some(cell), x, y);
         }
+        changed
     }
 
-    fn get_neighbors(&self, x: usize, y: usize) -> Vec<Cell> {
-        let mut neighbors: Vec<Cell> = vec![];
-        if x < self.size.0 && y < self.size.1 {
-            //left
-            if x > 0 {
-                neighbors.push(self.cells[x - 1][y]);
-            }
-            //right
+    // Every cell the rule changes in this generation, column by column.
+    // Collecting them first spares copying the grid to read from.
+    fn transitions(&self) -> Vec<(usize, usize, Cell)> {
+        let mut transitions = vec![];
+        for x in 0..self.size.0 {
+            for y in 0..self.size.1 {
+                if let Some(cell) = self.transition(x, y) {
+                    transitions.push((x, y, cell));
+                }
+            }
+        }
+        transitions
+    }
+
+    // Same as `step_synchronous` without noise, with bands of rows evaluated
+    // on the rayon pool. Every band reads its halo rows from the unchanged
+    // grid, so the result doesn't depend on how the rows are split.
+    #[cfg(feature = "parallel")]
+    fn step_bands(&mut self) -> bool {
+        use rayon::prelude::*;
+
+        let grid = &*self;
+        let changes: Vec<(usize, usize, Cell)> = (0..grid.size.1)
+            .step_by(BAND_HEIGHT)
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .flat_map_iter(|top| {
+                let bottom = (top + BAND_HEIGHT).min(grid.size.1);
+                let mut band = vec![];
+                for y in top..bottom {
+                    for x in 0..grid.size.0 {
+                        if let Some(cell) = grid.transition(x, y) {
+                            band.push((x, y, cell));
+                        }
+                    }
+                }
+                band
+            })
+            .collect();
+
+        let changed = !changes.is_empty();
+        for (x, y, cell) in changes {
+            self.cells[x][y] = cell;
+        }
+        changed
+    }
+
+    fn step_bits(&mut self) -> bool {
+        let bits = self.pack();
+        let next = bits.step(self.rule.birth, self.rule.survive);
+
+        let mut changes = vec![];
+        for x in 0..self.size.0 {
+            for y in 0..self.size.1 {
+                match (bits.get(x, y), next.get(x, y)) {
+                    (false, true) => changes.push((x, y, Some(self.birth_colour(x, y)))),
+                    (true, false) => changes.push((x, y, None)),
+                    _ => {}
+                }
+            }
+        }
+        let changed = !changes.is_empty();
+        for (x, y, birth) in changes {
+            let cell = &mut self.cells[x][y];
+            match birth {
+                Some(colour) => {
+                    cell.state = CellState::Alive;
+                    cell.colour = colour;
+                }
+                None => cell.state = CellState::Dead,
+            }
+        }
+        changed
+    }
+
+    // Synchronous step that writes into the previous generation's buffer and
+    // only evaluates tiles whose neighbourhood is neither still nor period 2.
+    // With the `parallel` feature the tiles are evaluated on the rayon pool.
+    fn step_tiles(&mut self) -> bool {
+        let mut back = std::mem::take(&mut self.tiles.back);
+        if back.len() != self.size.0 {
+            back = self.cells.clone();
+        }
+        let count = self.tiles.count();
+        #[cfg(feature = "parallel")]
+        let steps: Vec<_> = {
+            use rayon::prelude::*;
+
+            (0..count)
+                .into_par_iter()
+                .map(|tile| self.step_tile(tile, &back))
+                .collect()
+        };
+        #[cfg(not(feature = "parallel"))]
+        let steps: Vec<_> = (0..count).map(|tile| self.step_tile(tile, &back)).collect();
+
+        let mut changed = vec![false; count];
+        let mut changed_since_two_ago = vec![false; count];
+        for (tile, (tile_changed, tile_changed_since_two_ago, writes)) in
+            steps.into_iter().enumerate()
+        {
+            changed[tile] = tile_changed;
+            changed_since_two_ago[tile] = tile_changed_since_two_ago;
+            for (x, y, cell) in writes {
+                back[x][y] = cell;
+            }
+        }
+
+        std::mem::swap(&mut self.cells, &mut back);
+        self.tiles.back = back;
+        let any = changed.contains(&true);
+        self.tiles.update(changed, changed_since_two_ago);
+        any
+    }
+
+    // Whether `tile` changes and differs from two generations ago, with the
+    // cells of `back` it has to overwrite.
+    fn step_tile(
+        &self,
+        tile: usize,
+        back: &[Vec<Cell>],
+    ) -> (bool, bool, Vec<(usize, usize, Cell)>) {
+        let valid = self.tiles.is_valid();
+        // The tile itself didn't change either, so `back` already holds it.
+        if valid && !self.tiles.neighbourhood_changed(tile) {
+            return (false, false, vec![]);
+        }
+        if valid && !self.tiles.neighbourhood_changed_since_two_ago(tile) {
+            return (self.tiles.changed(tile), false, vec![]);
+        }
+
+        let (xs, ys) = self.tiles.bounds(tile, self.size);
+        let (mut changed, mut changed_since_two_ago) = (false, !valid);
+        let mut writes = vec![];
+        for x in xs {
+            for y in ys.clone() {
+                let current = self.cells[x][y];
+                let next = self.transition(x, y).unwrap_or(current);
+                changed |= next != current;
+                if next != back[x][y] {
+                    changed_since_two_ago = true;
+                    writes.push((x, y, next));
+                }
+            }
+        }
+        (changed, changed_since_two_ago, writes)
+    }
+
+    // Blocks that would hang over the edge of the grid are left alone.
+    fn step_blocks(&mut self, table: &[u8; 16], offset: usize) -> bool {
+        let mut changed = false;
+        for y in (offset..self.size.1.saturating_sub(1)).step_by(2) {
+            for x in (offset..self.size.0.saturating_sub(1)).step_by(2) {
+                let corners = [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)];
+                let block = corners
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (cx, cy))| self.cells[*cx][*cy].state == CellState::Alive)
+                    .fold(0, |block, (bit, _)| block | 1 << bit);
+                let next = table[block];
+                changed |= next as usize != block;
+                for (bit, (cx, cy)) in corners.iter().enumerate() {
+                    let cell = self.get_mut_cell(*cx, *cy);
+                    cell.state = if next & (1 << bit) != 0 {
+                        CellState::Alive
+                    } else {
+                        CellState::Dead
+                    };
+                    cell.colour = 0;
+                }
+            }
+        }
+        changed
+    }
+
+    // The cell the rule turns (x, y) into, or `None` if it stays as it is.
+    fn transition(&self, x: usize, y: usize) -> Option<Cell> {
+        if let Some(rule) = &self.rule_file {
+            let current_cell = self.get_cell(x, y);
+            let state = Self::state_of(current_cell);
+            let next = rule.next_state(state, &self.neighbor_states(x, y));
+            return if next == state {
+                None
+            } else {
+                Some(Self::with_state(current_cell, next))
+            };
+        }
+
+        let alive_neighbors = self.alive_neighbors(x, y);
+        let current_cell = self.get_cell(x, y);
+        if current_cell.state == CellState::Alive && !self.rule.survives(alive_neighbors) {
+            return Some(Cell {
+                state: CellState::Dead,
+                ..current_cell
+            });
+        }
+        if current_cell.state == CellState::Dead && self.rule.births(alive_neighbors) {
+            return Some(Cell {
+                state: CellState::Alive,
+                colour: self.birth_colour(x, y),
+                ..current_cell
+            });
+        }
+        None
+    }
+
+    // Tells whether the transition was applied.
+    fn apply_transition(&mut self, transition: Option<Cell>, x: usize, y: usize) -> bool {
+        match transition {
+            Some(cell) if self.transition_allowed() => {
+                *self.get_mut_cell(x, y) = cell;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn transition_allowed(&mut self) -> bool {
+        if self.noise.transition_probability >= 1.0 {
+            return true;
+        }
+
+        let allowed = self.rng.gen_bool(self.noise.transition_probability);
+        if !allowed {
+            self.noise.suppressed += 1;
+        }
+        allowed
+    }
+
+    fn flip_noise(&mut self) -> bool {
+        let flipped = self.noise.flipped;
+        for x in 0..self.size.0 {
+            for y in 0..self.size.1 {
+                if self.rng.gen_bool(self.noise.flip_rate) {
+                    let cell = self.get_mut_cell(x, y);
+                    cell.state = match cell.state {
+                        CellState::Alive => CellState::Dead,
+                        CellState::Dead => CellState::Alive,
+                    };
+                    cell.colour = 0;
+                    self.noise.flipped += 1;
+                }
+            }
+        }
+        self.noise.flipped != flipped
+    }
+
+    fn kill_cell(&mut self, x: usize, y: usize) {
+        self.update_cell(x, y, CellState::Dead);
+    }
+
+    pub fn revive_cell(&mut self, x: usize, y: usize) {
+        self.update_cell(x, y, CellState::Alive);
+    }
+
+    fn get_cell(&self, x: usize, y: usize) -> Cell {
+        self.cells[x][y]
+    }
+
+    fn update_cell(&mut self, x: usize, y: usize, state: CellState) {
+        if x < self.size.0 && y < self.size.1 {
+            self.cells[x][y].state = state;
+            self.cells[x][y].colour = match (state, &self.rule_file) {
+                // Live cells of a rule file are its states 1 and up.
+                (CellState::Alive, Some(rule)) => self.brush.min((rule.states() - 2) as u8),
+                (CellState::Alive, None) => self.brush,
+                (CellState::Dead, _) => 0,
+            };
+            self.tiles.invalidate();
+        }
+    }
+
+    // Newborns take the majority colour of their parents. When every parent
+    // has a different colour (QuadLife) the colour none of them has wins.
+    fn birth_colour(&self, x: usize, y: usize) -> u8 {
+        let colours = self.variant.colours() as usize;
+        if colours == 1 {
+            return 0;
+        }
+
+        let mut counts = [0usize; 4];
+        self.get_neighbors(x, y)
+            .iter()
+            .filter(|cell| cell.state == CellState::Alive)
+            .for_each(|cell| counts[cell.colour as usize % colours] += 1);
+
+        let counts = &counts[..colours];
+        let max = counts.iter().max().copied().unwrap_or(0);
+        if max == 1 {
+            if let Some(missing) = counts.iter().position(|count| *count == 0) {
+                return missing as u8;
+            }
+        }
+        counts.iter().position(|count| *count == max).unwrap_or(0) as u8
+    }
+
+    fn get_neighbors(&self, x: usize, y: usize) -> Vec<Cell> {
+        let mut neighbors: Vec<Cell> = vec![];
+        if x < self.size.0 && y < self.size.1 {
+            //left
+            if x > 0 {
+                neighbors.push(self.cells[x - 1][y]);
+            }
+            //right
             if x + 1 < self.size.0 {
                 neighbors.push(self.cells[x + 1][y]);
             }
@@ -1127,6 +2235,59 @@ impl Grid {
         neighbors
     }
 
+    // Multi-state values of the neighbours in the order N, NE, E, SE, S, SW,
+    // W, NW. Cells past the edge count as state 0.
+    fn neighbor_states(&self, x: usize, y: usize) -> [u8; 8] {
+        let offsets: [(isize, isize); 8] = [
+            (0, -1),
+            (1, -1),
+            (1, 0),
+            (1, 1),
+            (0, 1),
+            (-1, 1),
+            (-1, 0),
+            (-1, -1),
+        ];
+        let mut states = [0; 8];
+        for (i, (dx, dy)) in offsets.iter().enumerate() {
+            let nx = x.checked_add_signed(*dx);
+            let ny = y.checked_add_signed(*dy);
+            if let (Some(nx), Some(ny)) = (nx, ny) {
+                states[i] = self.get_state(nx, ny);
+            }
+        }
+        states
+    }
+
+    fn state_of(cell: Cell) -> u8 {
+        match cell.state {
+            CellState::Alive => cell.colour + 1,
+            CellState::Dead => 0,
+        }
+    }
+
+    fn clamp_state(&self, state: u8) -> u8 {
+        match &self.rule_file {
+            Some(rule) => state.min((rule.states() - 1) as u8),
+            None => state,
+        }
+    }
+
+    fn with_state(cell: Cell, state: u8) -> Cell {
+        match state {
+            0 => Cell {
+                state: CellState::Dead,
+                colour: 0,
+                ..cell
+            },
+            _ => Cell {
+                state: CellState::Alive,
+                colour: state - 1,
+                ..cell
+            },
+        }
+    }
+
     fn alive_neighbors(&self, x: usize, y: usize) -> usize {
         let neighbors = self.get_neighbors(x, y);
         neighbors
@@ -1136,8 +2297,7 @@ impl Grid {
     }
 
     fn get_mut_cell(&mut self, x: usize, y: usize) -> &mut Cell {
-        let cell = &mut self.cells[x][y];
-        cell
+        &mut self.cells[x][y]
     }
 
     fn set_cells_alive(&mut self, coords: Vec<(usize, usize)>) {
@@ -1146,6 +2306,7 @@ impl Grid {
             .for_each(|coord| self.update_cell(coord.0, coord.1, CellState::Alive));
     }
 
+    #[allow(dead_code)]
     fn print(&self) {
         let size = self.size;
         for y in 0..size.1 {
@@ -1161,6 +2322,248 @@ impl Grid {
     }
 }
 
+// Views of the cells for the JS frontend. Native code reads them with
+// `cells`, `live`, `born_cells`, `died_cells`, `selection` and
+// `rule_file_colours` instead.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl Grid {
+    /// 1 for live and 0 for dead cells, row by row.
+    pub fn get_cells(&self) -> Vec<u8> {
+        let mut flat = Vec::with_capacity(self.size.0 * self.size.1);
+        for y in 0..self.size.1 {
+            for x in 0..self.size.0 {
+                flat.push((self.cells[x][y].state == CellState::Alive) as u8);
+            }
+        }
+        flat
+    }
+
+    pub fn born(&self) -> *const u32 {
+        self.born.as_ptr()
+    }
+
+    pub fn died(&self) -> *const u32 {
+        self.died.as_ptr()
+    }
+
+    /// `rule_file_colours` as flat RGB triples.
+    #[wasm_bindgen(js_name = rule_file_colours)]
+    pub fn rule_file_colours_flat(&self) -> Vec<u8> {
+        self.rule_file_colours().iter().flatten().copied().collect()
+    }
+
+    /// The selection as `[x, y, width, height]`, empty when nothing is
+    /// selected.
+    #[wasm_bindgen(js_name = selection)]
+    pub fn selection_flat(&self) -> Vec<usize> {
+        self.selection()
+            .map(|(x, y, width, height)| vec![x, y, width, height])
+            .unwrap_or_default()
+    }
+}
+
+impl Grid {
+    /// Cells born in the last `step_forward`, while changes are recorded.
+    pub fn born_cells(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.born.iter().map(move |&i| self.coordinates(i))
+    }
+
+    /// Cells that died in the last `step_forward`.
+    pub fn died_cells(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.died.iter().map(move |&i| self.coordinates(i))
+    }
+
+    fn coordinates(&self, index: u32) -> (i64, i64) {
+        let width = self.size.0 as i64;
+        (index as i64 % width, index as i64 / width)
+    }
+
+    /// `@COLORS` of the loaded rule file, one RGB triple per state.
+    pub fn rule_file_colours(&self) -> &[[u8; 3]] {
+        self.rule_file.as_ref().map_or(&[], |rule| rule.colours())
+    }
+
+    /// The selection as `(x, y, width, height)`.
+    pub fn selection(&self) -> Option<(usize, usize, usize, usize)> {
+        self.selection
+    }
+
+    /// Replaces the grid with the window of `pattern` whose top left cell is
+    /// (`left`, `top`), and takes on its rule and generation. A rule in the
+    /// pattern replaces any Margolus rule or rule file.
+    pub fn show_macrocell(
+        &mut self,
+        pattern: &Macrocell,
+        left: i64,
+        top: i64,
+    ) -> Result<(), MacrocellError> {
+        if let Some(rule) = pattern.rule() {
+            self.rule = rule
+                .parse()
+                .map_err(|_| MacrocellError::UnsupportedRule(rule.to_string()))?;
+            self.margolus = None;
+            self.rule_file = None;
+        }
+        self.generation = pattern.generation() as usize;
+        self.reset();
+        for (x, y, state) in pattern.crop(left, top, self.size.0 as u64, self.size.1 as u64) {
+            let (x, y) = ((x - left) as usize, (y - top) as usize);
+            self.cells[x][y] = Self::with_state(self.cells[x][y], self.clamp_state(state));
+        }
+        self.tiles.invalidate();
+        Ok(())
+    }
+
+    pub fn clipboard(&self) -> Option<&Clipboard> {
+        self.clipboard.as_ref()
+    }
+
+    pub fn set_clipboard(&mut self, clipboard: Clipboard) {
+        self.clipboard = Some(clipboard);
+    }
+
+    // `points` must be on the grid.
+    fn draw(&mut self, points: &[(usize, usize)], mode: DrawMode, state: u8) -> usize {
+        let state = self.clamp_state(state);
+        let mut changed = 0;
+        for (x, y) in points.iter().copied() {
+            let current = Self::state_of(self.cells[x][y]);
+            let next = mode.apply(current, state);
+            if next != current {
+                self.cells[x][y] = Self::with_state(self.cells[x][y], next);
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.tiles.invalidate();
+        }
+        changed
+    }
+
+    pub(crate) fn set_life_rule(&mut self, rule: LifeRule) {
+        self.rule = rule;
+        self.tiles.invalidate();
+    }
+
+    pub(crate) fn snapshot(&self) -> Snapshot {
+        let mut states = Vec::with_capacity(self.size.0 * self.size.1);
+        for y in 0..self.size.1 {
+            for x in 0..self.size.0 {
+                states.push(Self::state_of(self.cells[x][y]));
+            }
+        }
+        Snapshot {
+            width: self.size.0,
+            height: self.size.1,
+            generation: self.generation,
+            rule: self.rule,
+            variant: self.variant,
+            brush: self.brush,
+            schedule: self.schedule,
+            alpha: self.alpha,
+            transition_probability: self.noise.transition_probability,
+            flip_rate: self.noise.flip_rate,
+            backend: self.backend,
+            incremental: self.incremental,
+            rng: (self.rng.seed(), self.rng.state()),
+            margolus: self.margolus.map(|rule| rule.to_string()),
+            rule_file: self
+                .rule_file
+                .as_ref()
+                .map(|rule| rule.source().to_string()),
+            states,
+        }
+    }
+
+    pub(crate) fn from_snapshot(snapshot: Snapshot) -> Result<Grid, SnapshotError> {
+        let mut grid = Grid::new(snapshot.width, snapshot.height);
+        grid.generation = snapshot.generation;
+        grid.rule = snapshot.rule;
+        grid.variant = snapshot.variant;
+        grid.brush = snapshot.brush;
+        grid.schedule = snapshot.schedule;
+        grid.set_alpha(snapshot.alpha);
+        grid.set_transition_probability(snapshot.transition_probability);
+        grid.set_flip_rate(snapshot.flip_rate);
+        grid.backend = snapshot.backend;
+        grid.incremental = snapshot.incremental;
+        grid.rng = SeededRng::resume(snapshot.rng.0, snapshot.rng.1);
+        if let Some(rule) = &snapshot.margolus {
+            grid.set_margolus_rule(rule)
+                .map_err(|e| SnapshotError::Invalid(e.to_string()))?;
+        }
+        if let Some(text) = &snapshot.rule_file {
+            grid.load_rule_file(text)
+                .map_err(|e| SnapshotError::Invalid(e.to_string()))?;
+        }
+        for (i, state) in snapshot.states.iter().enumerate() {
+            let (x, y) = (i % grid.size.0, i / grid.size.0);
+            grid.cells[x][y] = Self::with_state(grid.cells[x][y], grid.clamp_state(*state));
+        }
+        grid.tiles.invalidate();
+        Ok(grid)
+    }
+
+    /// Every cell with its coordinates, row by row.
+    pub fn cells(&self) -> impl Iterator<Item = (i64, i64, Cell)> + '_ {
+        (0..self.size.1).flat_map(move |y| {
+            (0..self.size.0).map(move |x| (x as i64, y as i64, self.cells[x][y]))
+        })
+    }
+
+    /// Coordinates of the live cells, row by row.
+    pub fn live(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.cells()
+            .filter(|(_, _, cell)| cell.state == CellState::Alive)
+            .map(|(x, y, _)| (x, y))
+    }
+
+    pub fn cell(&self, x: i64, y: i64) -> Option<Cell> {
+        self.index(x, y).map(|(x, y)| self.cells[x][y])
+    }
+
+    pub fn set_alive(&mut self, x: i64, y: i64, colour: u8) -> Result<(), GridError> {
+        let (x, y) = self.index(x, y).ok_or(GridError::OutOfBounds(x, y))?;
+        self.revive_cells(vec![(x, y)], colour);
+        Ok(())
+    }
+
+    pub fn set_dead(&mut self, x: i64, y: i64) -> Result<(), GridError> {
+        let (x, y) = self.index(x, y).ok_or(GridError::OutOfBounds(x, y))?;
+        self.kill_cell(x, y);
+        Ok(())
+    }
+
+    fn index(&self, x: i64, y: i64) -> Option<(usize, usize)> {
+        let x = usize::try_from(x).ok().filter(|x| *x < self.size.0)?;
+        let y = usize::try_from(y).ok().filter(|y| *y < self.size.1)?;
+        Some((x, y))
+    }
+
+    pub(crate) fn is_alive(&self, x: usize, y: usize) -> bool {
+        x < self.size.0 && y < self.size.1 && self.cells[x][y].state == CellState::Alive
+    }
+
+    pub(crate) fn live_cells(&self) -> Vec<(usize, usize)> {
+        let mut live = vec![];
+        for x in 0..self.size.0 {
+            for y in 0..self.size.1 {
+                if self.cells[x][y].state == CellState::Alive {
+                    live.push((x, y));
+                }
+            }
+        }
+        live
+    }
+
+    pub(crate) fn revive_cells(&mut self, coords: Vec<(usize, usize)>, colour: u8) {
+        self.brush = colour;
+        self.set_cells_alive(coords);
+        self.brush = 0;
+    }
+}
+
 impl Display for Grid {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let size = self.size;
@@ -1183,6 +2586,8 @@ impl Display for Grid {
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "image")]
+    use crate::animation::AnimationFormat;
 
     #[test]
     fn step_forward_blinker_test() {
@@ -1295,4 +2700,625 @@ mod tests {
         let cell = neighbors[4];
         assert_eq!(cell.state, CellState::Alive);
     }
+
+    #[test]
+    fn immigration_birth_takes_majority_colour_test() {
+        let mut grid = Grid::new(10, 10);
+        grid.set_variant(Variant::Immigration);
+        grid.rle_spawn(1, 1, "o", 1).unwrap();
+        grid.rle_spawn(2, 1, "2o", 0).unwrap();
+        grid.step_forward();
+        let cell = grid.get_cell(2, 2);
+        assert_eq!(cell.state, CellState::Alive);
+        assert_eq!(cell.colour, 0);
+    }
+
+    #[test]
+    fn quad_life_birth_takes_missing_colour_test() {
+        let mut grid = Grid::new(10, 10);
+        grid.set_variant(Variant::QuadLife);
+        grid.rle_spawn(1, 1, "o", 0).unwrap();
+        grid.rle_spawn(2, 1, "o", 1).unwrap();
+        grid.rle_spawn(3, 1, "o", 3).unwrap();
+        grid.step_forward();
+        let cell = grid.get_cell(2, 2);
+        assert_eq!(cell.state, CellState::Alive);
+        assert_eq!(cell.colour, 2);
+    }
+
+    #[test]
+    fn spawn_pattern_colour_test() {
+        let mut grid = Grid::new(10, 10);
+        grid.spawn_pattern(Pattern::Block, 2, 2, 3);
+        assert_eq!(grid.get_cell(3, 3).colour, 3);
+        grid.revive_cell(6, 6);
+        assert_eq!(grid.get_cell(6, 6).colour, 0);
+    }
+
+    #[test]
+    fn noise_is_reproducible_from_seed_test() {
+        let mut first = Grid::new(30, 30);
+        first.spawn_pattern(Pattern::RPentomino, 12, 12, 0);
+        first.set_seed(42);
+        first.set_transition_probability(0.8);
+        first.set_flip_rate(0.01);
+        let mut second = first.clone();
+
+        for _ in 0..20 {
+            first.step_forward();
+            second.step_forward();
+        }
+        assert_eq!(first, second);
+        assert!(first.suppressed_transitions() > 0);
+        assert!(first.flipped_cells() > 0);
+
+        let mut other_seed = Grid::new(30, 30);
+        other_seed.spawn_pattern(Pattern::RPentomino, 12, 12, 0);
+        other_seed.set_seed(7);
+        other_seed.set_transition_probability(0.8);
+        other_seed.set_flip_rate(0.01);
+        for _ in 0..20 {
+            other_seed.step_forward();
+        }
+        assert_ne!(first.live_cells(), other_seed.live_cells());
+    }
+
+    #[test]
+    fn suppressed_transitions_keep_state_test() {
+        let mut grid = Grid::new(10, 10);
+        grid.spawn_blinker(5, 5);
+        grid.set_transition_probability(0.0);
+        grid.step_forward();
+        assert_eq!(grid.live_cells(), vec![(5, 4), (5, 5), (5, 6)]);
+        assert_eq!(grid.suppressed_transitions(), 2);
+    }
+
+    #[test]
+    fn sweep_schedule_test() {
+        let mut grid = Grid::new(10, 10);
+        grid.set_schedule(UpdateSchedule::Sweep);
+        grid.set_cells_alive(vec![(4, 5), (5, 5), (6, 5)]);
+        grid.step_forward();
+        // (5, 4) is born first and already counts as a parent for (6, 4).
+        assert_eq!(
+            grid.live_cells(),
+            vec![(4, 5), (5, 4), (5, 5), (5, 6), (6, 4), (6, 5), (6, 6)]
+        );
+    }
+
+    #[test]
+    fn asynchronous_schedules_are_seeded_test() {
+        for schedule in [
+            UpdateSchedule::RandomOrder,
+            UpdateSchedule::AlphaAsynchronous,
+        ] {
+            let mut first = Grid::new(30, 30);
+            first.spawn_pattern(Pattern::Acorn, 10, 10, 0);
+            first.set_schedule(schedule);
+            first.set_alpha(0.5);
+            first.set_seed(3);
+            let mut second = first.clone();
+            for _ in 0..10 {
+                first.step_forward();
+                second.step_forward();
+            }
+            assert_eq!(first, second);
+        }
+    }
+
+    #[test]
+    fn zero_alpha_freezes_grid_test() {
+        let mut grid = Grid::new(10, 10);
+        grid.spawn_blinker(5, 5);
+        grid.set_schedule(UpdateSchedule::AlphaAsynchronous);
+        grid.set_alpha(0.0);
+        grid.step_forward();
+        assert_eq!(grid.live_cells(), vec![(5, 4), (5, 5), (5, 6)]);
+    }
+
+    #[test]
+    fn margolus_billiard_ball_test() {
+        let mut grid = Grid::new(10, 10);
+        grid.set_margolus_preset(MargolusPreset::BilliardBall);
+        grid.revive_cell(2, 2);
+        grid.step_forward();
+        assert_eq!(grid.live_cells(), vec![(3, 3)]);
+        grid.step_forward();
+        assert_eq!(grid.live_cells(), vec![(4, 4)]);
+        assert_eq!(grid.generation(), 2);
+    }
+
+    #[test]
+    fn margolus_step_back_test() {
+        for preset in [MargolusPreset::BilliardBall, MargolusPreset::Critters] {
+            let mut grid = Grid::new(16, 12);
+            grid.set_margolus_rule(preset.rule()).unwrap();
+            assert!(grid.is_reversible());
+            grid.randomize();
+            let start = grid.live_cells();
+            for _ in 0..25 {
+                grid.step_forward();
+            }
+            assert_ne!(grid.live_cells(), start);
+            for _ in 0..25 {
+                assert!(grid.step_back());
+            }
+            assert_eq!(grid.live_cells(), start);
+            assert!(!grid.step_back());
+        }
+    }
+
+    #[test]
+    fn rule_file_step_test() {
+        let mut grid = Grid::new(10, 10);
+        grid.load_rule_file(
+            "@RULE Wire
+@TABLE
+n_states:3
+neighborhood:vonNeumann
+symmetries:rotate4
+var a={0,1}
+var b={a}
+var c={a}
+var d={0,1,2}
+var e={d}
+var f={d}
+var g={d}
+# a signal (2) moves along wire (1) and leaves wire behind
+1,2,a,b,c,2
+2,d,e,f,g,1
+",
+        )
+        .unwrap();
+        assert_eq!(grid.rule_file_name(), Some("Wire".to_string()));
+        grid.set_state(5, 3, 1);
+        grid.set_state(5, 4, 1);
+        grid.set_state(5, 5, 2);
+        grid.step_forward();
+        assert_eq!(grid.get_state(5, 3), 1);
+        assert_eq!(grid.get_state(5, 4), 2);
+        assert_eq!(grid.get_state(5, 5), 1);
+        assert_eq!(grid.get_cell(5, 4).colour, 1);
+
+        // States the rule doesn't have are clamped, not stepped.
+        grid.set_state(3, 3, 5);
+        assert_eq!(grid.get_state(3, 3), 2);
+        grid.set_alive(7, 7, 4).unwrap();
+        assert_eq!(grid.get_state(7, 7), 2);
+        grid.set_clipboard(Clipboard::new(2, 1, vec![5, 9]).unwrap());
+        assert!(grid.paste(0, 0, Transform::Identity, PasteMode::Copy));
+        assert_eq!((grid.get_state(0, 0), grid.get_state(1, 0)), (2, 2));
+        let pattern = Macrocell::from_cells(&[(0, 0, 7)], None, 0);
+        grid.show_macrocell(&pattern, 0, 0).unwrap();
+        assert!(grid.rule_file_name().is_some());
+        assert_eq!(grid.get_state(0, 0), 2);
+        grid.step_forward();
+        let rule = grid.rule_file.clone().unwrap();
+        grid.load_rule_file(rule.source()).unwrap();
+        assert!((0..10).all(|x| (0..10).all(|y| grid.get_state(x, y) < 3)));
+    }
+
+    // Half the cells alive, the same ones for the same seed.
+    fn soup(width: usize, height: usize, seed: u64) -> Grid {
+        let mut rng = SeededRng::new(seed);
+        let alive = (0..width)
+            .flat_map(|x| (0..height).map(move |y| (x, y)))
+            .filter(|_| rng.gen_bool(0.5))
+            .collect();
+        let mut grid = Grid::new(width, height);
+        grid.revive_cells(alive, 0);
+        grid
+    }
+
+    fn demo_grid() -> Grid {
+        let mut grid = Grid::new(200, 200);
+        grid.spawn_glider_gun(75, 15);
+        grid.spawn_pulsar(10, 35);
+        grid.spawn_pentadecanthlon(25, 55);
+        grid.spawn_acorn(25, 175);
+        grid.spawn_r_pentomino(180, 25);
+        grid.spawn_glider_loop(100, 100);
+        grid.spawn_pulsar(100, 100);
+        grid.spawn_infinite_growth_1(175, 175);
+        grid
+    }
+
+    #[test]
+    fn incremental_step_matches_full_step_test() {
+        let mut incremental = demo_grid();
+        let mut full = demo_grid();
+        full.set_incremental(false);
+        for generation in 0..60 {
+            incremental.step_forward();
+            full.step_forward();
+            assert_eq!(incremental.cells, full.cells, "generation {}", generation);
+        }
+
+        incremental.revive_cell(60, 60);
+        full.revive_cell(60, 60);
+        incremental.step_forward();
+        full.step_forward();
+        assert_eq!(incremental.cells, full.cells);
+    }
+
+    #[test]
+    fn period_two_tiles_are_inactive_test() {
+        let mut grid = Grid::new(100, 100);
+        grid.spawn_block(10, 10);
+        // A period 2 oscillator under B38/S1234.
+        grid.rle_spawn(70, 70, "2o$o2bo$3bo$b2o!", 0).unwrap();
+        let start = grid.live_cells();
+        for _ in 0..4 {
+            grid.step_forward();
+        }
+        assert_eq!(grid.active_tiles(), 1);
+        assert_eq!(grid.live_cells(), start);
+    }
+
+    #[test]
+    fn bit_parallel_backend_matches_cells_test() {
+        let mut bits = demo_grid();
+        bits.set_backend(Backend::BitParallel);
+        let mut cells = demo_grid();
+        for _ in 0..30 {
+            bits.step_forward();
+            cells.step_forward();
+        }
+        assert_eq!(bits.cells, cells.cells);
+
+        // step_n unpacks only once, at the end.
+        let mut bits = demo_grid();
+        bits.set_backend(Backend::BitParallel);
+        let mut cells = bits.clone();
+        cells.set_backend(Backend::Cells);
+        assert_eq!(bits.step_n(12), cells.step_n(12));
+        assert_eq!(bits.generation(), cells.generation());
+        assert_eq!(bits.cells, cells.cells);
+        bits.step_forward();
+        cells.step_forward();
+        assert_eq!(bits.cells, cells.cells);
+
+        let mut bits = Grid::new(70, 20);
+        bits.set_variant(Variant::QuadLife);
+        bits.randomize();
+        bits.set_backend(Backend::BitParallel);
+        let mut cells = bits.clone();
+        cells.set_backend(Backend::Cells);
+        for _ in 0..10 {
+            bits.step_forward();
+            cells.step_forward();
+        }
+        assert_eq!(bits.cells, cells.cells);
+    }
+
+    #[test]
+    fn incremental_step_matches_single_threaded_test() {
+        for (width, height) in [(64, 64), (97, 203), (250, 7)] {
+            let mut incremental = soup(width, height, 7);
+            let mut single = incremental.clone();
+            for generation in 0..20 {
+                incremental.step_forward();
+                single.step_synchronous();
+                assert_eq!(incremental.cells, single.cells, "generation {}", generation);
+            }
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_step_matches_single_threaded_test() {
+        for (width, height) in [(64, 64), (97, 203), (250, 7)] {
+            // Without tiles a plain step is split into bands.
+            let mut parallel = soup(width, height, 11);
+            parallel.set_incremental(false);
+            let mut single = parallel.clone();
+            for generation in 0..20 {
+                parallel.step_forward();
+                single.step_synchronous();
+                assert_eq!(parallel.cells, single.cells, "generation {}", generation);
+            }
+        }
+    }
+
+    #[test]
+    fn record_changes_test() {
+        let mut grid = Grid::new(10, 10);
+        grid.set_record_changes(true);
+        grid.spawn_block(4, 4);
+        grid.revive_cell(1, 1);
+        grid.step_forward();
+        assert_eq!(grid.died_cells().collect::<Vec<_>>(), vec![(1, 1)]);
+        assert_eq!(grid.born_cells().count(), 0);
+
+        grid.set_record_changes(false);
+        assert_eq!(grid.died_count(), 0);
+    }
+
+    #[test]
+    fn step_n_stops_when_settled_test() {
+        let mut grid = Grid::new(20, 20);
+        grid.spawn_block(5, 5);
+        grid.revive_cell(15, 15);
+        assert_eq!(grid.step_n(10), 2);
+        assert_eq!(grid.generation(), 2);
+
+        let mut grid = Grid::new(20, 20);
+        grid.revive_cell(15, 15);
+        assert_eq!(grid.step_n(10), 1);
+        assert_eq!(grid.step_for_ms(5.0), 1);
+
+        let mut grid = Grid::new(20, 20);
+        grid.rle_spawn(5, 5, "2o$o2bo$3bo$b2o!", 0).unwrap();
+        assert_eq!(grid.step_n(10), 10);
+
+        // A blinker keeps changing even once its tiles are period 2.
+        let mut grid = Grid::new(40, 40);
+        grid.set_rule("B3/S23").unwrap();
+        grid.rle_spawn(20, 20, "3o!", 0).unwrap();
+        assert_eq!(grid.step_n(10), 10);
+        grid.set_backend(Backend::BitParallel);
+        assert_eq!(grid.step_n(10), 10);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn png_round_trip_test() {
+        let mut grid = Grid::new(12, 8);
+        grid.spawn_pattern(Pattern::Glider1, 3, 2, 0);
+        let bytes = grid.to_png(1, &[]).unwrap();
+
+        let mut loaded = Grid::new(12, 8);
+        loaded.load_image(&bytes, 0.2).unwrap();
+        assert_eq!(loaded.live_cells(), grid.live_cells());
+        loaded.load_image(&bytes, -0.2).unwrap();
+        assert_eq!(loaded.population(), 12 * 8 - 5);
+
+        assert!(grid.to_png(3, &[]).unwrap().len() > bytes.len());
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn record_animation_test() {
+        let mut grid = Grid::new(20, 20);
+        grid.rle_spawn(5, 5, "2o$o2bo$3bo$b2o!", 0).unwrap();
+        let mut animation = Animation::new(AnimationFormat::Apng);
+        animation.set_cell_px(2);
+        animation.set_region(2, 2, 10, 8);
+        let bytes = grid.clone().record_animation(4, &animation).unwrap();
+        let reader = png::Decoder::new(&bytes[..]).read_info().unwrap();
+        let info = reader.info();
+        assert_eq!((info.width, info.height), (20, 16));
+        assert_eq!(info.animation_control.unwrap().num_frames, 5);
+
+        animation = Animation::new(AnimationFormat::Gif);
+        let bytes = grid.record_animation(4, &animation).unwrap();
+        let mut decoder = gif::DecodeOptions::new().read_info(&bytes[..]).unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (80, 80));
+        let first = decoder.read_next_frame().unwrap().unwrap().width;
+        assert_eq!(first, 80);
+        let mut frames = 1;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert!(frame.width < 80 && frame.height < 80);
+            frames += 1;
+        }
+        assert_eq!(frames, 5);
+
+        // A still life is one frame shown for the whole run.
+        let mut still = Grid::new(10, 10);
+        still.rle_spawn(3, 3, "2o$2o!", 0).unwrap();
+        let bytes = still.record_animation(9, &animation).unwrap();
+        let mut decoder = gif::DecodeOptions::new().read_info(&bytes[..]).unwrap();
+        assert_eq!(decoder.read_next_frame().unwrap().unwrap().delay, 100);
+        assert!(decoder.read_next_frame().unwrap().is_none());
+
+        // A full 256 colour palette.
+        animation.set_colours(&[7; 768]);
+        assert!(still.record_animation(1, &animation).is_ok());
+    }
+
+    #[test]
+    fn region_to_svg_test() {
+        let mut grid = Grid::new(20, 20);
+        grid.rle_spawn(5, 5, "3o$o!", 1).unwrap();
+        let mut style = SvgStyle::new();
+        let svg = grid.region_to_svg(4, 4, 6, 6, &style);
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+        assert!(svg.contains("width=\"60\" height=\"60\""));
+        assert!(svg.contains("<g fill=\"#c51b1b\">"));
+        assert!(svg.contains("<rect x=\"10\" y=\"10\" width=\"30\" height=\"10\"/>"));
+        assert!(svg.contains("<rect x=\"10\" y=\"20\" width=\"10\" height=\"10\"/>"));
+        assert!(!svg.contains("<path") && !svg.contains("<text"));
+
+        style.set_phases(3);
+        style.set_grid_lines(true);
+        style.set_labels(5);
+        let svg = grid.region_to_svg(4, 4, 6, 6, &style);
+        assert_eq!(svg.matches("<g transform").count(), 3);
+        assert_eq!(svg.matches("<path").count(), 3);
+        assert!(svg.contains(">5</text>"));
+        assert_eq!(grid.generation(), 0);
+    }
+
+    #[test]
+    fn snapshot_round_trip_test() {
+        let mut grid = Grid::new(30, 20);
+        grid.set_rule("B3/S23").unwrap();
+        grid.set_variant(Variant::QuadLife);
+        grid.rle_spawn(5, 5, "bo$2bo$3o!", 2).unwrap();
+        grid.rle_spawn(20, 10, "3o!", 0).unwrap();
+        grid.set_seed(42);
+        grid.set_flip_rate(0.01);
+        grid.step_n(3);
+
+        let bytes = grid.save_snapshot();
+        let mut loaded = Grid::load_snapshot(&bytes).unwrap();
+        assert_eq!(loaded.generation(), 3);
+        assert_eq!(loaded.rule(), "B3/S23");
+        assert_eq!(loaded.variant(), Variant::QuadLife);
+        let live = |grid: &Grid| -> Vec<_> {
+            grid.cells()
+                .filter(|(_, _, cell)| cell.state == CellState::Alive)
+                .collect()
+        };
+        assert_eq!(live(&loaded), live(&grid));
+
+        // The random stream carries on where it was saved.
+        grid.step_n(5);
+        loaded.step_n(5);
+        assert_eq!(live(&loaded), live(&grid));
+        assert_eq!(loaded.save_snapshot(), grid.save_snapshot());
+
+        assert!(Grid::load_snapshot(&bytes[..bytes.len() - 1]).is_err());
+        assert_eq!(
+            Grid::load_snapshot(b"x = 3, y = 1\n3o!"),
+            Err(SnapshotError::NotASnapshot)
+        );
+    }
+
+    #[cfg(feature = "share")]
+    #[test]
+    fn share_string_test() {
+        let mut grid = Grid::new(200, 200);
+        grid.spawn_glider_gun(75, 15);
+        grid.spawn_pulsar(10, 35);
+        grid.set_rule("B3/S23").unwrap();
+        let text = grid.to_share_string().unwrap();
+        assert!(text.len() < 200, "{} characters", text.len());
+        let shared = Grid::from_share_string(&text).unwrap();
+        assert_eq!(shared.live_cells(), grid.live_cells());
+        assert_eq!((shared.width(), shared.height()), (200, 200));
+        assert_eq!(shared.rule(), "B3/S23");
+
+        let mut noisy = Grid::new(300, 300);
+        noisy.set_seed(7);
+        noisy.set_flip_rate(0.5);
+        noisy.step_forward();
+        assert!(matches!(
+            noisy.to_share_string(),
+            Err(ShareError::TooLarge(_))
+        ));
+        assert!(Grid::from_share_string("AAAA").is_err());
+
+        // A short link must not be able to declare a huge grid.
+        let mut hostile = grid.snapshot();
+        hostile.width = u32::MAX as usize;
+        hostile.height = 1 << 20;
+        hostile.states.clear();
+        let text = share::encode(&hostile.encode()).unwrap();
+        assert!(text.len() < 200);
+        assert!(matches!(
+            Grid::from_share_string(&text),
+            Err(ShareError::Snapshot(SnapshotError::Invalid(_)))
+        ));
+    }
+
+    #[test]
+    fn macrocell_test() {
+        let glider = "[M2] (golly 4.2)\n#R B3/S23\n#G 12\n.*$..*$***$\n4 0 0 0 1\n";
+        let mut grid = Grid::new(9, 9);
+        grid.load_macrocell(glider).unwrap();
+        assert_eq!(grid.rule(), "B3/S23");
+        assert_eq!(grid.generation(), 12);
+        assert_eq!(
+            grid.live_cells(),
+            vec![(3, 5), (4, 3), (4, 5), (5, 4), (5, 5)]
+        );
+
+        let text = grid.to_macrocell();
+        assert!(text.starts_with("[M2]") && text.contains("#G 12"));
+        let mut copy = Grid::new(9, 9);
+        copy.load_macrocell(&text).unwrap();
+        assert_eq!(copy.live_cells(), grid.live_cells());
+
+        let pattern: Macrocell = text.parse().unwrap();
+        copy.show_macrocell(&pattern, 4, 4).unwrap();
+        assert_eq!(copy.live_cells(), vec![(0, 1), (1, 0), (1, 1)]);
+
+        let wire = "[M2]\n#R WireWorld\n.*$\n4 1 0 0 0\n";
+        assert_eq!(
+            grid.load_macrocell(wire),
+            Err(MacrocellError::UnsupportedRule("WireWorld".to_string()))
+        );
+
+        // Only the B/S rule fits in `#R`, and loading one clears the others.
+        grid.set_margolus_preset(MargolusPreset::Critters);
+        assert!(!grid.to_macrocell().contains("#R"));
+        grid.load_macrocell(glider).unwrap();
+        assert_eq!(grid.margolus_rule(), None);
+        assert!(grid.to_macrocell().contains("#R B3/S23"));
+    }
+
+    #[test]
+    fn clipboard_test() {
+        let mut grid = Grid::new(8, 8);
+        grid.rle_spawn(1, 1, "3o$2bo!", 0).unwrap();
+        assert!(!grid.copy_selection());
+        grid.select(0, 0, 4, 3);
+        assert_eq!(grid.selection(), Some((0, 0, 4, 3)));
+        assert!(grid.cut_selection());
+        assert_eq!(grid.population(), 0);
+        assert_eq!(
+            grid.clipboard_to_rle().unwrap(),
+            "x = 3, y = 2, rule = B38/S1234\n3o$2bo!\n"
+        );
+
+        grid.paste(4, 4, Transform::Rotate90, PasteMode::Or);
+        assert_eq!(grid.live_cells(), vec![(4, 7), (5, 5), (5, 6), (5, 7)]);
+        grid.paste(4, 4, Transform::Rotate90, PasteMode::Xor);
+        assert_eq!(grid.population(), 0);
+
+        grid.set_state(7, 7, 3);
+        grid.set_state(6, 6, 1);
+        grid.paste(6, 6, Transform::Identity, PasteMode::Copy);
+        assert_eq!(grid.live_cells(), vec![(7, 7)]);
+        assert_eq!(grid.get_state(7, 7), 1);
+
+        grid.select(6, 6, 10, 10);
+        assert_eq!(grid.selection(), Some((6, 6, 2, 2)));
+        grid.select(9, 9, 2, 2);
+        assert!(grid.selection().is_none());
+    }
+
+    #[test]
+    fn drawing_test() {
+        let mut grid = Grid::new(10, 10);
+        assert_eq!(grid.draw_line(0, 0, 9, 0, DrawMode::Set, 1), 10);
+        assert_eq!(grid.draw_line(0, 0, 9, 0, DrawMode::Set, 1), 0);
+        assert_eq!(grid.draw_line(0, 0, 20, 20, DrawMode::Toggle, 2), 10);
+        assert_eq!(grid.get_state(0, 0), 0);
+        assert_eq!(grid.get_state(5, 5), 2);
+
+        grid.reset();
+        assert_eq!(grid.draw_rectangle(2, 2, 5, 4, DrawMode::Set, 1), 14);
+        // The inside of the box, but not the outside, is one dead region.
+        assert_eq!(grid.flood_fill(4, 4, 3), 6);
+        assert_eq!(grid.get_state(5, 4), 3);
+        assert_eq!(grid.get_state(0, 0), 0);
+        assert_eq!(grid.flood_fill(2, 2, 0), 14);
+        assert_eq!(grid.population(), 6);
+
+        assert_eq!(grid.invert_region(0, 0, 10, 10, 1), 100);
+        assert_eq!(grid.population(), 94);
+        assert_eq!(grid.fill_rectangle(0, 0, 10, 10, DrawMode::Clear, 0), 94);
+        assert_eq!(grid.fill_ellipse(0, 0, 5, 5, DrawMode::Set, 1), 21);
+        assert_eq!(grid.draw_ellipse(0, 0, 5, 5, DrawMode::Clear, 0), 12);
+        assert_eq!(grid.population(), 9);
+
+        // Huge shapes are clipped before their cells are listed.
+        grid.reset();
+        assert_eq!(
+            grid.fill_rectangle(0, 0, 1_000_000, 1_000_000, DrawMode::Set, 1),
+            100
+        );
+        assert_eq!(grid.draw_line(0, 5, usize::MAX, 5, DrawMode::Clear, 0), 10);
+
+        // States are clamped to a loaded rule file's states.
+        grid.load_rule_file("@RULE X\n@TABLE\nn_states:3\nneighborhood:vonNeumann\n")
+            .unwrap();
+        grid.fill_ellipse(0, 0, 10, 10, DrawMode::Set, 200);
+        grid.flood_fill(9, 9, 9);
+        assert!(grid
+            .cells()
+            .all(|(x, y, _)| grid.get_state(x as usize, y as usize) < 3));
+    }
 }
@@ -0,0 +1,167 @@
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::backend::Backend;
+use crate::grid::Grid;
+use crate::life_rule::LifeRule;
+use crate::snapshot::{Snapshot, MAX_CELLS};
+use crate::update_schedule::UpdateSchedule;
+use crate::variant::Variant;
+
+/// What a `Grid` serializes as: its settings and only the live cells.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Grid")]
+struct GridData {
+    width: usize,
+    height: usize,
+    generation: usize,
+    rule: LifeRule,
+    variant: Variant,
+    brush: u8,
+    schedule: UpdateSchedule,
+    alpha: f64,
+    transition_probability: f64,
+    flip_rate: f64,
+    backend: Backend,
+    incremental: bool,
+    seed: u64,
+    rng_state: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    margolus: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rule_file: Option<String>,
+    /// `(x, y, colour)` of every live cell, row by row.
+    live: Vec<(usize, usize, u8)>,
+}
+
+impl Serialize for Grid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let snapshot = self.snapshot();
+        let width = snapshot.width.max(1);
+        let live = snapshot
+            .states
+            .iter()
+            .enumerate()
+            .filter(|(_, state)| **state != 0)
+            .map(|(i, state)| (i % width, i / width, state - 1))
+            .collect();
+        GridData {
+            width: snapshot.width,
+            height: snapshot.height,
+            generation: snapshot.generation,
+            rule: snapshot.rule,
+            variant: snapshot.variant,
+            brush: snapshot.brush,
+            schedule: snapshot.schedule,
+            alpha: snapshot.alpha,
+            transition_probability: snapshot.transition_probability,
+            flip_rate: snapshot.flip_rate,
+            backend: snapshot.backend,
+            incremental: snapshot.incremental,
+            seed: snapshot.rng.0,
+            rng_state: snapshot.rng.1,
+            margolus: snapshot.margolus,
+            rule_file: snapshot.rule_file,
+            live,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Grid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = GridData::deserialize(deserializer)?;
+        let cells = data
+            .width
+            .checked_mul(data.height)
+            .filter(|cells| *cells <= MAX_CELLS)
+            .ok_or_else(|| D::Error::custom("grid is too large"))?;
+        let mut states = vec![0; cells];
+        for (x, y, colour) in data.live {
+            if x >= data.width || y >= data.height {
+                return Err(D::Error::custom(format!(
+                    "live cell ({}, {}) is outside the grid",
+                    x, y
+                )));
+            }
+            states[y * data.width + x] = colour
+                .checked_add(1)
+                .ok_or_else(|| D::Error::custom("colour is too large"))?;
+        }
+        Grid::from_snapshot(Snapshot {
+            width: data.width,
+            height: data.height,
+            generation: data.generation,
+            rule: data.rule,
+            variant: data.variant,
+            brush: data.brush,
+            schedule: data.schedule,
+            alpha: data.alpha,
+            transition_probability: data.transition_probability,
+            flip_rate: data.flip_rate,
+            backend: data.backend,
+            incremental: data.incremental,
+            rng: (data.seed, data.rng_state),
+            margolus: data.margolus,
+            rule_file: data.rule_file,
+            states,
+        })
+        .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::Cell;
+    use crate::cell_state::CellState;
+    use crate::pattern::Pattern;
+
+    #[test]
+    fn grid_json_round_trip_test() {
+        let mut grid = Grid::new(40, 30);
+        grid.set_rule("B36/S23").unwrap();
+        grid.set_schedule(UpdateSchedule::Sweep);
+        grid.spawn_pattern(Pattern::Glider1, 5, 5, 1);
+        grid.step_n(2);
+
+        let json = serde_json::to_string(&grid).unwrap();
+        assert!(json.contains("\"rule\":\"B36/S23\""));
+        assert!(json.contains("\"schedule\":\"Sweep\""));
+        assert!(json.len() < 600, "{}", json);
+
+        let mut loaded: Grid = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.live_cells(), grid.live_cells());
+        assert_eq!(loaded.generation(), 2);
+        assert_eq!(loaded.schedule(), UpdateSchedule::Sweep);
+        grid.step_n(3);
+        loaded.step_n(3);
+        assert_eq!(loaded.save_snapshot(), grid.save_snapshot());
+
+        let outside = json.replace("\"live\":[", "\"live\":[[40,0,0],");
+        assert!(serde_json::from_str::<Grid>(&outside).is_err());
+        let huge = json.replace("\"width\":40", "\"width\":4294967296");
+        assert_ne!(huge, json);
+        assert!(serde_json::from_str::<Grid>(&huge).is_err());
+    }
+
+    #[test]
+    fn value_types_round_trip_test() {
+        let rule: LifeRule = serde_json::from_str("\"23/3\"").unwrap();
+        assert_eq!(serde_json::to_string(&rule).unwrap(), "\"B3/S23\"");
+        assert!(serde_json::from_str::<LifeRule>("\"B9/S\"").is_err());
+
+        let cell = Cell {
+            id: 3,
+            state: CellState::Alive,
+            colour: 2,
+        };
+        let json = serde_json::to_string(&cell).unwrap();
+        assert_eq!(serde_json::from_str::<Cell>(&json).unwrap(), cell);
+
+        let json = serde_json::to_string(&Pattern::GliderGun).unwrap();
+        assert_eq!(json, "\"GliderGun\"");
+        let pattern: Pattern = serde_json::from_str(&json).unwrap();
+        assert_eq!(pattern.to_string(), "GliderGun");
+    }
+}
@@ -0,0 +1,84 @@
+use std::fmt::{Display, Formatter};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ImageError {
+    Encode(String),
+    Decode(String),
+}
+
+impl Display for ImageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageError::Encode(message) => write!(f, "could not encode image: {}", message),
+            ImageError::Decode(message) => write!(f, "could not decode image: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+#[cfg(feature = "wasm")]
+impl From<ImageError> for JsValue {
+    fn from(error: ImageError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+pub fn encode_png(width: usize, height: usize, rgb: &[u8]) -> Result<Vec<u8>, ImageError> {
+    let error = |e: png::EncodingError| ImageError::Encode(e.to_string());
+    let mut bytes = vec![];
+    let mut encoder = png::Encoder::new(&mut bytes, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(error)?;
+    writer.write_image_data(rgb).map_err(error)?;
+    writer.finish().map_err(error)?;
+    Ok(bytes)
+}
+
+/// Width, height and row-major luminance in 0..=1 of a PNG. Transparent
+/// pixels are treated as black.
+pub fn decode_luminance(bytes: &[u8]) -> Result<(usize, usize, Vec<f64>), ImageError> {
+    let error = |e: png::DecodingError| ImageError::Decode(e.to_string());
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(error)?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(error)?;
+
+    let channels = info.color_type.samples();
+    let luminance = buffer[..info.buffer_size()]
+        .chunks_exact(channels)
+        .map(|pixel| {
+            let (rgb, alpha) = match pixel.len() {
+                1 => ([pixel[0]; 3], 255),
+                2 => ([pixel[0]; 3], pixel[1]),
+                3 => ([pixel[0], pixel[1], pixel[2]], 255),
+                _ => ([pixel[0], pixel[1], pixel[2]], pixel[3]),
+            };
+            let light = 0.2126 * rgb[0] as f64 + 0.7152 * rgb[1] as f64 + 0.0722 * rgb[2] as f64;
+            light / 255.0 * (alpha as f64 / 255.0)
+        })
+        .collect();
+    Ok((info.width as usize, info.height as usize, luminance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn png_round_trip_test() {
+        let rgb = [255, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0x8f, 0x11];
+        let bytes = encode_png(2, 2, &rgb).unwrap();
+        let (width, height, luminance) = decode_luminance(&bytes).unwrap();
+        assert_eq!((width, height), (2, 2));
+        assert!(luminance[0] > 0.99);
+        assert_eq!(luminance[1], 0.0);
+        assert!(luminance[3] > 0.3 && luminance[3] < 0.5);
+        assert!(decode_luminance(b"not a png").is_err());
+    }
+}
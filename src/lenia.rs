@@ -0,0 +1,392 @@
+use std::sync::Arc;
+
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use strum::{Display, IntoEnumIterator};
+use strum_macros::EnumIter;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// Kernels bigger than this are convolved in frequency space, smaller ones
+// are cheaper to sum directly.
+const FFT_RADIUS_THRESHOLD: usize = 6;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Copy, Clone, Display, EnumIter)]
+pub enum LeniaCreature {
+    Orbium,
+}
+
+impl LeniaCreature {
+    /// (radius, mu, sigma, dt) the creature was found with.
+    fn parameters(&self) -> (usize, f32, f32, f32) {
+        match self {
+            LeniaCreature::Orbium => (13, 0.15, 0.015, 0.1),
+        }
+    }
+
+    fn cells(&self) -> &'static [&'static [f32]] {
+        match self {
+            LeniaCreature::Orbium => ORBIUM,
+        }
+    }
+}
+
+/// Continuous-state universe running Lenia on a torus. Cell values are in
+/// `0.0..=1.0` and stored row-major so JS can wrap them in a `Float32Array`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Lenia {
+    cells: Vec<f32>,
+    size: (usize, usize),
+    radius: usize,
+    mu: f32,
+    sigma: f32,
+    dt: f32,
+    kernel: Vec<(usize, usize, f32)>,
+    spectrum: Option<Spectrum>,
+}
+
+struct Spectrum {
+    kernel: Vec<Complex<f32>>,
+    row_forward: Arc<dyn Fft<f32>>,
+    row_inverse: Arc<dyn Fft<f32>>,
+    column_forward: Arc<dyn Fft<f32>>,
+    column_inverse: Arc<dyn Fft<f32>>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Lenia {
+    /// Sizes of zero are treated as one.
+    pub fn new(size_x: usize, size_y: usize) -> Self {
+        let (size_x, size_y) = (size_x.max(1), size_y.max(1));
+        let (radius, mu, sigma, dt) = LeniaCreature::Orbium.parameters();
+        let mut lenia = Self {
+            cells: vec![0.0; size_x * size_y],
+            size: (size_x, size_y),
+            radius,
+            mu,
+            sigma,
+            dt,
+            kernel: vec![],
+            spectrum: None,
+        };
+        lenia.build_kernel();
+        lenia
+    }
+
+    pub fn width(&self) -> usize {
+        self.size.0
+    }
+
+    pub fn height(&self) -> usize {
+        self.size.1
+    }
+
+    pub fn radius(&self) -> usize {
+        self.radius
+    }
+
+    pub fn set_radius(&mut self, radius: usize) {
+        self.radius = radius.max(1);
+        self.build_kernel();
+    }
+
+    pub fn set_mu(&mut self, mu: f32) {
+        self.mu = mu;
+    }
+
+    pub fn set_sigma(&mut self, sigma: f32) {
+        self.sigma = sigma;
+    }
+
+    pub fn set_dt(&mut self, dt: f32) {
+        self.dt = dt;
+    }
+
+    pub fn mass(&self) -> f32 {
+        self.cells.iter().sum()
+    }
+
+    pub fn reset(&mut self) {
+        self.cells.iter_mut().for_each(|cell| *cell = 0.0);
+    }
+
+    /// Fills a `size` square at (x, y) with uniform noise from `seed`.
+    pub fn spawn_soup(&mut self, x: usize, y: usize, size: usize, seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for dy in 0..size {
+            for dx in 0..size {
+                let index = self.wrapped_index(x + dx, y + dy);
+                self.cells[index] = rng.gen_range(0.0..1.0);
+            }
+        }
+    }
+
+    /// Stamps a creature with its top left corner at (x, y) and switches the
+    /// universe over to the parameters it needs to survive.
+    pub fn spawn_creature(&mut self, creature: LeniaCreature, x: usize, y: usize) {
+        let (radius, mu, sigma, dt) = creature.parameters();
+        self.mu = mu;
+        self.sigma = sigma;
+        self.dt = dt;
+        if self.radius != radius {
+            self.set_radius(radius);
+        }
+
+        for (dy, row) in creature.cells().iter().enumerate() {
+            for (dx, value) in row.iter().enumerate() {
+                let index = self.wrapped_index(x + dx, y + dy);
+                self.cells[index] = *value;
+            }
+        }
+    }
+
+    pub fn step_forward(&mut self) {
+        let potential = if self.spectrum.is_some() {
+            self.potential_fft()
+        } else {
+            self.potential_direct()
+        };
+
+        for (cell, u) in self.cells.iter_mut().zip(potential) {
+            let growth = 2.0 * (-(u - self.mu).powi(2) / (2.0 * self.sigma.powi(2))).exp() - 1.0;
+            *cell = (*cell + self.dt * growth).clamp(0.0, 1.0);
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl Lenia {
+    pub fn get_cells(&self) -> *const f32 {
+        self.cells.as_ptr()
+    }
+}
+
+impl Lenia {
+    /// The cell values row by row, each between 0 and 1.
+    pub fn cells(&self) -> &[f32] {
+        &self.cells
+    }
+
+    fn wrapped_index(&self, x: usize, y: usize) -> usize {
+        (y % self.size.1) * self.size.0 + (x % self.size.0)
+    }
+
+    fn build_kernel(&mut self) {
+        let radius = self.radius as isize;
+        let (width, height) = (self.size.0 as isize, self.size.1 as isize);
+        let mut kernel = vec![];
+        let mut total = 0.0;
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let r = ((dx * dx + dy * dy) as f32).sqrt() / self.radius as f32;
+                if r <= 0.0 || r >= 1.0 {
+                    continue;
+                }
+                let weight = (4.0 - 1.0 / (r * (1.0 - r))).exp();
+                total += weight;
+                kernel.push((
+                    dx.rem_euclid(width) as usize,
+                    dy.rem_euclid(height) as usize,
+                    weight,
+                ));
+            }
+        }
+        kernel
+            .iter_mut()
+            .for_each(|(_, _, weight)| *weight /= total);
+        self.kernel = kernel;
+
+        self.spectrum = if self.radius > FFT_RADIUS_THRESHOLD && !self.cells.is_empty() {
+            Some(self.build_spectrum())
+        } else {
+            None
+        };
+    }
+
+    fn build_spectrum(&self) -> Spectrum {
+        let (width, height) = self.size;
+        let mut planner = FftPlanner::new();
+        let mut spectrum = Spectrum {
+            kernel: vec![Complex::new(0.0, 0.0); width * height],
+            row_forward: planner.plan_fft_forward(width),
+            row_inverse: planner.plan_fft_inverse(width),
+            column_forward: planner.plan_fft_forward(height),
+            column_inverse: planner.plan_fft_inverse(height),
+        };
+
+        let mut field = vec![Complex::new(0.0, 0.0); width * height];
+        for (dx, dy, weight) in &self.kernel {
+            field[dy * width + dx].re += weight;
+        }
+        fft_2d(
+            &mut field,
+            self.size,
+            &spectrum.row_forward,
+            &spectrum.column_forward,
+        );
+        spectrum.kernel = field;
+        spectrum
+    }
+
+    fn potential_direct(&self) -> Vec<f32> {
+        let (width, height) = self.size;
+        let mut potential = vec![0.0; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                potential[y * width + x] = self
+                    .kernel
+                    .iter()
+                    .map(|(dx, dy, weight)| weight * self.cells[self.wrapped_index(x + dx, y + dy)])
+                    .sum();
+            }
+        }
+        potential
+    }
+
+    fn potential_fft(&self) -> Vec<f32> {
+        let spectrum = match &self.spectrum {
+            Some(spectrum) => spectrum,
+            None => return self.potential_direct(),
+        };
+
+        let mut field: Vec<Complex<f32>> = self
+            .cells
+            .iter()
+            .map(|value| Complex::new(*value, 0.0))
+            .collect();
+        fft_2d(
+            &mut field,
+            self.size,
+            &spectrum.row_forward,
+            &spectrum.column_forward,
+        );
+        // The kernel is point symmetric so correlation and convolution agree.
+        field
+            .iter_mut()
+            .zip(&spectrum.kernel)
+            .for_each(|(value, kernel)| *value *= kernel);
+        fft_2d(
+            &mut field,
+            self.size,
+            &spectrum.row_inverse,
+            &spectrum.column_inverse,
+        );
+
+        let scale = (self.size.0 * self.size.1) as f32;
+        field.iter().map(|value| value.re / scale).collect()
+    }
+}
+
+fn fft_2d(
+    field: &mut [Complex<f32>],
+    size: (usize, usize),
+    row_fft: &Arc<dyn Fft<f32>>,
+    column_fft: &Arc<dyn Fft<f32>>,
+) {
+    let (width, height) = size;
+    field
+        .chunks_exact_mut(width)
+        .for_each(|row| row_fft.process(row));
+
+    let mut column = vec![Complex::new(0.0, 0.0); height];
+    for x in 0..width {
+        for y in 0..height {
+            column[y] = field[y * width + x];
+        }
+        column_fft.process(&mut column);
+        for y in 0..height {
+            field[y * width + x] = column[y];
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_lenia_creatures_as_string() -> String {
+    let creatures: Vec<String> = LeniaCreature::iter()
+        .map(|creature| creature.to_string())
+        .collect();
+    creatures.join(",")
+}
+
+#[rustfmt::skip]
+const ORBIUM: &[&[f32]] = &[
+    &[0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.1, 0.14, 0.1, 0.0, 0.0, 0.03, 0.03, 0.0, 0.0, 0.3, 0.0, 0.0, 0.0, 0.0],
+    &[0.0, 0.0, 0.0, 0.0, 0.0, 0.08, 0.24, 0.3, 0.3, 0.18, 0.14, 0.15, 0.16, 0.15, 0.09, 0.2, 0.0, 0.0, 0.0, 0.0],
+    &[0.0, 0.0, 0.0, 0.0, 0.0, 0.15, 0.34, 0.44, 0.46, 0.38, 0.18, 0.14, 0.11, 0.13, 0.19, 0.18, 0.45, 0.0, 0.0, 0.0],
+    &[0.0, 0.0, 0.0, 0.0, 0.06, 0.13, 0.39, 0.5, 0.5, 0.37, 0.06, 0.0, 0.0, 0.0, 0.02, 0.16, 0.68, 0.0, 0.0, 0.0],
+    &[0.0, 0.0, 0.0, 0.11, 0.17, 0.17, 0.33, 0.4, 0.38, 0.28, 0.14, 0.0, 0.0, 0.0, 0.0, 0.0, 0.18, 0.42, 0.0, 0.0],
+    &[0.0, 0.0, 0.09, 0.18, 0.13, 0.06, 0.08, 0.26, 0.32, 0.32, 0.27, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.82, 0.0, 0.0],
+    &[0.27, 0.0, 0.16, 0.12, 0.0, 0.0, 0.0, 0.25, 0.38, 0.44, 0.45, 0.34, 0.0, 0.0, 0.0, 0.0, 0.0, 0.22, 0.17, 0.0],
+    &[0.0, 0.07, 0.2, 0.02, 0.0, 0.0, 0.0, 0.31, 0.48, 0.57, 0.6, 0.57, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.49, 0.0],
+    &[0.0, 0.59, 0.19, 0.0, 0.0, 0.0, 0.0, 0.2, 0.57, 0.69, 0.76, 0.76, 0.49, 0.0, 0.0, 0.0, 0.0, 0.0, 0.36, 0.0],
+    &[0.0, 0.58, 0.19, 0.0, 0.0, 0.0, 0.0, 0.0, 0.67, 0.83, 0.9, 0.92, 0.87, 0.12, 0.0, 0.0, 0.0, 0.0, 0.22, 0.07],
+    &[0.0, 0.0, 0.46, 0.0, 0.0, 0.0, 0.0, 0.0, 0.7, 0.93, 1.0, 1.0, 1.0, 0.61, 0.0, 0.0, 0.0, 0.0, 0.18, 0.11],
+    &[0.0, 0.0, 0.82, 0.0, 0.0, 0.0, 0.0, 0.0, 0.47, 1.0, 1.0, 0.98, 1.0, 0.96, 0.27, 0.0, 0.0, 0.0, 0.19, 0.1],
+    &[0.0, 0.0, 0.46, 0.0, 0.0, 0.0, 0.0, 0.0, 0.25, 1.0, 1.0, 0.84, 0.92, 0.97, 0.54, 0.14, 0.04, 0.1, 0.21, 0.05],
+    &[0.0, 0.0, 0.0, 0.4, 0.0, 0.0, 0.0, 0.0, 0.09, 0.8, 1.0, 0.82, 0.8, 0.85, 0.63, 0.31, 0.18, 0.19, 0.2, 0.01],
+    &[0.0, 0.0, 0.0, 0.36, 0.1, 0.0, 0.0, 0.0, 0.05, 0.54, 0.86, 0.79, 0.74, 0.72, 0.6, 0.39, 0.28, 0.24, 0.13, 0.0],
+    &[0.0, 0.0, 0.0, 0.01, 0.3, 0.07, 0.0, 0.0, 0.08, 0.36, 0.64, 0.7, 0.64, 0.6, 0.51, 0.39, 0.29, 0.19, 0.04, 0.0],
+    &[0.0, 0.0, 0.0, 0.0, 0.1, 0.24, 0.14, 0.1, 0.15, 0.29, 0.45, 0.53, 0.52, 0.46, 0.4, 0.31, 0.21, 0.08, 0.0, 0.0],
+    &[0.0, 0.0, 0.0, 0.0, 0.0, 0.08, 0.21, 0.21, 0.22, 0.29, 0.36, 0.39, 0.37, 0.33, 0.26, 0.18, 0.09, 0.0, 0.0, 0.0],
+    &[0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.03, 0.13, 0.19, 0.22, 0.24, 0.24, 0.23, 0.18, 0.13, 0.05, 0.0, 0.0, 0.0, 0.0],
+    &[0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.02, 0.06, 0.08, 0.09, 0.07, 0.05, 0.01, 0.0, 0.0, 0.0, 0.0, 0.0],
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kernel_is_normalized_test() {
+        let lenia = Lenia::new(64, 64);
+        let total: f32 = lenia.kernel.iter().map(|(_, _, weight)| weight).sum();
+        assert!((total - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn zero_size_test() {
+        let mut lenia = Lenia::new(0, 16);
+        assert_eq!((lenia.width(), lenia.height()), (1, 16));
+        lenia.spawn_soup(0, 0, 4, 1);
+        lenia.step_forward();
+    }
+
+    #[test]
+    fn fft_matches_direct_convolution_test() {
+        let mut lenia = Lenia::new(48, 40);
+        lenia.spawn_soup(5, 5, 20, 7);
+        assert!(lenia.spectrum.is_some());
+        let direct = lenia.potential_direct();
+        let fft = lenia.potential_fft();
+        direct
+            .iter()
+            .zip(fft.iter())
+            .for_each(|(a, b)| assert!((a - b).abs() < 1e-4));
+    }
+
+    #[test]
+    fn small_radius_uses_direct_convolution_test() {
+        let mut lenia = Lenia::new(32, 32);
+        lenia.set_radius(FFT_RADIUS_THRESHOLD);
+        assert!(lenia.spectrum.is_none());
+        lenia.spawn_soup(0, 0, 32, 1);
+        lenia.step_forward();
+        assert!(lenia.cells.iter().all(|cell| (0.0..=1.0).contains(cell)));
+    }
+
+    #[test]
+    fn orbium_survives_test() {
+        let mut lenia = Lenia::new(64, 64);
+        lenia.spawn_creature(LeniaCreature::Orbium, 20, 20);
+        let start = lenia.mass();
+        for _ in 0..200 {
+            lenia.step_forward();
+        }
+        let end = lenia.mass();
+        assert!(end > start * 0.5 && end < start * 1.5);
+    }
+}
@@ -1,10 +1,68 @@
+#[cfg(feature = "image")]
+mod animation;
+mod backend;
+mod bit_grid;
 mod cell;
 mod cell_state;
-mod utils;
+mod challenge;
+mod clipboard;
+mod draw;
+mod game;
+mod golly_rule;
 mod grid;
+#[cfg(feature = "serde")]
+mod grid_serde;
+#[cfg(feature = "image")]
+mod image;
+#[cfg(feature = "lenia")]
+mod lenia;
+mod life_rule;
+mod macrocell;
+mod margolus;
+mod noise;
 mod pattern;
+mod plaintext;
+mod rle;
+mod seeded_rng;
+#[cfg(feature = "share")]
+mod share;
+mod snapshot;
+mod svg;
+mod tiles;
+mod update_schedule;
+mod utils;
+mod variant;
+
+#[cfg(feature = "image")]
+pub use crate::animation::{Animation, AnimationFormat};
+pub use crate::backend::Backend;
+pub use crate::cell::Cell;
+pub use crate::cell_state::CellState;
+pub use crate::challenge::{Challenge, ChallengeError, Evaluation};
+pub use crate::clipboard::{Clipboard, ClipboardError, PasteMode, Transform};
+pub use crate::draw::DrawMode;
+pub use crate::game::{Game, GameError};
+pub use crate::golly_rule::RuleFileError;
+pub use crate::grid::{Grid, GridError};
+#[cfg(feature = "image")]
+pub use crate::image::ImageError;
+#[cfg(feature = "lenia")]
+pub use crate::lenia::{get_lenia_creatures_as_string, Lenia, LeniaCreature};
+pub use crate::life_rule::{LifeRule, ParseRuleError};
+pub use crate::macrocell::{Macrocell, MacrocellError};
+pub use crate::margolus::{MargolusPreset, ParseMargolusError};
+pub use crate::pattern::{get_patterns_as_string, Pattern};
+pub use crate::plaintext::{parse_plaintext, write_plaintext};
+pub use crate::rle::{parse_rle, parse_rle_rule, write_rle, ParseRleError};
+#[cfg(feature = "share")]
+pub use crate::share::{ShareError, MAX_SHARE_LENGTH};
+pub use crate::snapshot::{SnapshotError, MAX_CELLS};
+pub use crate::svg::SvgStyle;
+pub use crate::update_schedule::UpdateSchedule;
+pub use crate::variant::Variant;
 
 use rand::prelude::*;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
@@ -13,11 +71,13 @@ use wasm_bindgen::prelude::*;
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 extern "C" {
     fn alert(s: &str);
 }
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 pub fn greet() {
     alert("Hello, wasm-game-of-life!");
@@ -0,0 +1,125 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseRuleError(String);
+
+impl Display for ParseRuleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid rule: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseRuleError {}
+
+#[cfg(feature = "wasm")]
+impl From<ParseRuleError> for JsValue {
+    fn from(error: ParseRuleError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+/// Outer-totalistic two state rule. Bit n of `birth` and `survive` is set
+/// when n live neighbours give birth or keep a cell alive.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LifeRule {
+    pub birth: u16,
+    pub survive: u16,
+}
+
+impl LifeRule {
+    pub fn births(&self, neighbours: usize) -> bool {
+        self.birth & (1 << neighbours) != 0
+    }
+
+    pub fn survives(&self, neighbours: usize) -> bool {
+        self.survive & (1 << neighbours) != 0
+    }
+}
+
+impl Default for LifeRule {
+    // The rule this project has always used.
+    fn default() -> Self {
+        "B38/S1234".parse().unwrap()
+    }
+}
+
+/// Accepts `B3/S23` and the older `23/3` (survive/birth) notation.
+impl FromStr for LifeRule {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseRuleError(s.to_string());
+        let (first, second) = s.trim().split_once('/').ok_or_else(error)?;
+        let mask = |digits: &str| -> Result<u16, ParseRuleError> {
+            digits.chars().try_fold(0, |mask, c| match c.to_digit(10) {
+                Some(n) if n <= 8 => Ok(mask | 1 << n),
+                _ => Err(error()),
+            })
+        };
+
+        let strip = |part: &str, prefix: char| {
+            part.strip_prefix(prefix)
+                .or_else(|| part.strip_prefix(prefix.to_ascii_lowercase()))
+                .map(str::to_string)
+        };
+        match (strip(first, 'B'), strip(second, 'S')) {
+            (Some(birth), Some(survive)) => Ok(LifeRule {
+                birth: mask(&birth)?,
+                survive: mask(&survive)?,
+            }),
+            (None, None) => Ok(LifeRule {
+                birth: mask(second)?,
+                survive: mask(first)?,
+            }),
+            _ => Err(error()),
+        }
+    }
+}
+
+impl Display for LifeRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let digits = |mask: u16| -> String {
+            (0..=8)
+                .filter(|n| mask & (1 << n) != 0)
+                .map(|n| n.to_string())
+                .collect()
+        };
+        write!(f, "B{}/S{}", digits(self.birth), digits(self.survive))
+    }
+}
+
+/// Serialized in `B3/S23` notation.
+#[cfg(feature = "serde")]
+impl serde::Serialize for LifeRule {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for LifeRule {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rule = String::deserialize(deserializer)?;
+        rule.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rule_test() {
+        let life: LifeRule = "B3/S23".parse().unwrap();
+        assert_eq!(life, "23/3".parse().unwrap());
+        assert_eq!(life.to_string(), "B3/S23");
+        assert!(life.births(3) && life.survives(2) && !life.survives(4));
+        assert_eq!(LifeRule::default().to_string(), "B38/S1234");
+        assert!("B9/S23".parse::<LifeRule>().is_err());
+        assert!("B3S23".parse::<LifeRule>().is_err());
+    }
+}
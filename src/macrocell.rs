@@ -0,0 +1,500 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// Coordinates are i64, so the root may be at most 2^62 cells wide.
+const MAX_LEVEL: u32 = 62;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MacrocellError {
+    MissingHeader,
+    InvalidLine(usize, String),
+    UnsupportedRule(String),
+}
+
+impl Display for MacrocellError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MacrocellError::MissingHeader => write!(f, "macrocell files start with [M2]"),
+            MacrocellError::InvalidLine(line, reason) => write!(f, "line {}: {}", line, reason),
+            MacrocellError::UnsupportedRule(rule) => write!(f, "unsupported rule {}", rule),
+        }
+    }
+}
+
+impl std::error::Error for MacrocellError {}
+
+#[cfg(feature = "wasm")]
+impl From<MacrocellError> for JsValue {
+    fn from(error: MacrocellError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+enum Node {
+    /// An 8x8 two state leaf, bit x of row y set for live cells.
+    Bits([u8; 8]),
+    /// A 2x2 multistate leaf: nw, ne, sw, se.
+    States([u8; 4]),
+    /// Quadrants nw, ne, sw, se of a 2^level square. 0 is an empty quadrant.
+    Children(u32, [usize; 4]),
+}
+
+impl Node {
+    fn level(&self) -> u32 {
+        match self {
+            Node::Bits(_) => 3,
+            Node::States(_) => 1,
+            Node::Children(level, _) => *level,
+        }
+    }
+}
+
+/// A Golly Macrocell (`.mc`) pattern kept as its quadtree, so identical
+/// subpatterns are stored once and huge patterns can be cropped without
+/// being expanded. Cell (0, 0) is the middle of the root, as in Golly.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Macrocell {
+    rule: Option<String>,
+    generation: u64,
+    // Node n is line n of the file; nodes[0] stands in for empty quadrants.
+    nodes: Vec<Node>,
+    root: usize,
+    populations: Vec<u64>,
+}
+
+impl Macrocell {
+    /// Builds the quadtree of `(x, y, state)` cells. Only states 0 and 1
+    /// give the compact two state encoding.
+    pub fn from_cells(cells: &[(i64, i64, u8)], rule: Option<String>, generation: u64) -> Self {
+        let mut cells: Vec<_> = cells.iter().copied().filter(|cell| cell.2 != 0).collect();
+        let multistate = cells.iter().any(|cell| cell.2 > 1);
+        // The root is always a node of leaves, never a bare leaf.
+        let mut level = if multistate { 2 } else { 4 };
+        let fits = |level: u32, (x, y, _): &(i64, i64, u8)| {
+            let half = 1i64 << (level - 1);
+            (-half..half).contains(x) && (-half..half).contains(y)
+        };
+        while level < MAX_LEVEL && !cells.iter().all(|cell| fits(level, cell)) {
+            level += 1;
+        }
+        cells.retain(|cell| fits(level, cell));
+
+        let mut builder = Builder {
+            multistate,
+            nodes: vec![Node::Children(0, [0; 4])],
+            index: HashMap::new(),
+        };
+        let half = 1i64 << (level - 1);
+        let root = builder.build(level, -half, -half, cells);
+        Self::new(rule, generation, builder.nodes, root)
+    }
+
+    fn new(rule: Option<String>, generation: u64, nodes: Vec<Node>, root: usize) -> Self {
+        let mut populations = Vec::with_capacity(nodes.len());
+        populations.push(0);
+        for node in &nodes[1..] {
+            let population = match node {
+                Node::Bits(rows) => rows.iter().map(|row| row.count_ones() as u64).sum(),
+                Node::States(states) => states.iter().filter(|state| **state != 0).count() as u64,
+                Node::Children(_, children) => children
+                    .iter()
+                    .fold(0u64, |sum, child| sum.saturating_add(populations[*child])),
+            };
+            populations.push(population);
+        }
+        Self {
+            rule,
+            generation,
+            nodes,
+            root,
+            populations,
+        }
+    }
+
+    pub fn rule(&self) -> Option<&str> {
+        self.rule.as_deref()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Saturates at `u64::MAX`.
+    pub fn population(&self) -> u64 {
+        self.populations[self.root]
+    }
+
+    fn origin(&self) -> (i64, u32) {
+        match self.root {
+            0 => (0, 0),
+            root => {
+                let level = self.nodes[root].level();
+                (-(1i64 << (level - 1)), level)
+            }
+        }
+    }
+
+    /// Bounding box of the live cells as `(left, top, width, height)`.
+    pub fn bounds(&self) -> Option<(i64, i64, u64, u64)> {
+        let (origin, level) = self.origin();
+        let mut bounds = None;
+        self.extend_bounds(self.root, level, origin, origin, &mut bounds);
+        bounds.map(|(left, top, right, bottom)| {
+            (
+                left,
+                top,
+                (right - left) as u64 + 1,
+                (bottom - top) as u64 + 1,
+            )
+        })
+    }
+
+    // Widens `bounds` by the node at (x, y), skipping quadrants that can't
+    // push any edge further out.
+    fn extend_bounds(
+        &self,
+        node: usize,
+        level: u32,
+        x: i64,
+        y: i64,
+        bounds: &mut Option<(i64, i64, i64, i64)>,
+    ) {
+        if node == 0 {
+            return;
+        }
+        let size = 1i64 << level;
+        if let Some((left, top, right, bottom)) = *bounds {
+            if x >= left && y >= top && x + (size - 1) <= right && y + (size - 1) <= bottom {
+                return;
+            }
+        }
+        match self.nodes[node] {
+            Node::Children(_, children) => {
+                let half = size / 2;
+                for (i, child) in children.iter().enumerate() {
+                    let (dx, dy) = quadrant(i, half);
+                    self.extend_bounds(*child, level - 1, x + dx, y + dy, bounds);
+                }
+            }
+            _ => self.visit(node, x, y, &mut |cx, cy, _| {
+                *bounds = Some(match *bounds {
+                    Some((left, top, right, bottom)) => {
+                        (left.min(cx), top.min(cy), right.max(cx), bottom.max(cy))
+                    }
+                    None => (cx, cy, cx, cy),
+                });
+            }),
+        }
+    }
+
+    /// Live cells `(x, y, state)` in the `width` x `height` window whose top
+    /// left cell is (`left`, `top`).
+    pub fn crop(&self, left: i64, top: i64, width: u64, height: u64) -> Vec<(i64, i64, u8)> {
+        let (origin, level) = self.origin();
+        let right = left.saturating_add(width as i64);
+        let bottom = top.saturating_add(height as i64);
+        let mut cells = vec![];
+        self.crop_node(
+            self.root,
+            level,
+            (origin, origin),
+            (left, top, right, bottom),
+            &mut cells,
+        );
+        cells
+    }
+
+    fn crop_node(
+        &self,
+        node: usize,
+        level: u32,
+        (x, y): (i64, i64),
+        window: (i64, i64, i64, i64),
+        cells: &mut Vec<(i64, i64, u8)>,
+    ) {
+        let (left, top, right, bottom) = window;
+        let size = 1i64 << level;
+        if node == 0 || x >= right || y >= bottom || x + size <= left || y + size <= top {
+            return;
+        }
+        match self.nodes[node] {
+            Node::Children(_, children) => {
+                let half = size / 2;
+                for (i, child) in children.iter().enumerate() {
+                    let (dx, dy) = quadrant(i, half);
+                    self.crop_node(*child, level - 1, (x + dx, y + dy), window, cells);
+                }
+            }
+            _ => self.visit(node, x, y, &mut |cx, cy, state| {
+                if (left..right).contains(&cx) && (top..bottom).contains(&cy) {
+                    cells.push((cx, cy, state));
+                }
+            }),
+        }
+    }
+
+    // Calls `f` for every live cell of a leaf at (x, y).
+    fn visit(&self, node: usize, x: i64, y: i64, f: &mut impl FnMut(i64, i64, u8)) {
+        match self.nodes[node] {
+            Node::Bits(rows) => {
+                for (dy, row) in rows.iter().enumerate() {
+                    for dx in (0..8).filter(|dx| row & (1 << dx) != 0) {
+                        f(x + dx as i64, y + dy as i64, 1);
+                    }
+                }
+            }
+            Node::States(states) => {
+                for (i, state) in states.iter().enumerate().filter(|(_, s)| **s != 0) {
+                    let (dx, dy) = quadrant(i, 1);
+                    f(x + dx, y + dy, *state);
+                }
+            }
+            Node::Children(..) => {}
+        }
+    }
+}
+
+// Offset of quadrant `i` (nw, ne, sw, se) in a square of two `half`s.
+fn quadrant(i: usize, half: i64) -> (i64, i64) {
+    ((i % 2) as i64 * half, (i / 2) as i64 * half)
+}
+
+struct Builder {
+    multistate: bool,
+    nodes: Vec<Node>,
+    index: HashMap<Node, usize>,
+}
+
+impl Builder {
+    fn intern(&mut self, node: Node) -> usize {
+        let next = self.nodes.len();
+        let index = *self.index.entry(node).or_insert(next);
+        if index == next {
+            self.nodes.push(node);
+        }
+        index
+    }
+
+    fn build(&mut self, level: u32, x: i64, y: i64, cells: Vec<(i64, i64, u8)>) -> usize {
+        if cells.is_empty() {
+            return 0;
+        }
+        if level == 3 && !self.multistate {
+            let mut rows = [0u8; 8];
+            for (cx, cy, _) in cells {
+                rows[(cy - y) as usize] |= 1 << (cx - x);
+            }
+            return self.intern(Node::Bits(rows));
+        }
+        if level == 1 {
+            let mut states = [0u8; 4];
+            for (cx, cy, state) in cells {
+                states[((cy - y) * 2 + (cx - x)) as usize] = state;
+            }
+            return self.intern(Node::States(states));
+        }
+
+        let half = 1i64 << (level - 1);
+        let mut quadrants: [Vec<(i64, i64, u8)>; 4] = Default::default();
+        for cell in cells {
+            let i = ((cell.1 - y) / half * 2 + (cell.0 - x) / half) as usize;
+            quadrants[i].push(cell);
+        }
+        let mut children = [0; 4];
+        for (i, quadrant_cells) in quadrants.iter_mut().enumerate() {
+            let (dx, dy) = quadrant(i, half);
+            children[i] = self.build(level - 1, x + dx, y + dy, std::mem::take(quadrant_cells));
+        }
+        self.intern(Node::Children(level, children))
+    }
+}
+
+/// Reads `[M2]` files: `#R` and `#G` lines, 8x8 leaves such as `.*$..*$`
+/// and `level nw ne sw se` nodes, the last of which is the root.
+impl FromStr for Macrocell {
+    type Err = MacrocellError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().enumerate();
+        match lines.next() {
+            Some((_, header)) if header.starts_with("[M2]") => {}
+            _ => return Err(MacrocellError::MissingHeader),
+        }
+
+        let mut rule = None;
+        let mut generation = 0;
+        let mut nodes = vec![Node::Children(0, [0; 4])];
+        for (index, line) in lines {
+            let number = index + 1;
+            let invalid = |reason: &str| MacrocellError::InvalidLine(number, reason.to_string());
+            let line = line.trim();
+            if let Some(text) = line.strip_prefix("#R") {
+                rule = Some(text.trim().to_string());
+            } else if let Some(text) = line.strip_prefix("#G") {
+                generation = text
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid("generation is not a number"))?;
+            } else if line.is_empty() || line.starts_with('#') {
+                continue;
+            } else if line.starts_with(['.', '*', '$']) {
+                let mut rows = [0u8; 8];
+                let (mut x, mut y) = (0, 0);
+                for c in line.chars() {
+                    match c {
+                        '.' => x += 1,
+                        '*' if x < 8 && y < 8 => {
+                            rows[y] |= 1 << x;
+                            x += 1;
+                        }
+                        '$' => {
+                            x = 0;
+                            y += 1;
+                        }
+                        _ => return Err(invalid("bad 8x8 leaf")),
+                    }
+                }
+                nodes.push(Node::Bits(rows));
+            } else {
+                let numbers: Vec<u64> = line
+                    .split_whitespace()
+                    .map(|word| word.parse().map_err(|_| invalid("expected numbers")))
+                    .collect::<Result<_, _>>()?;
+                let (level, children) = match numbers[..] {
+                    [level, a, b, c, d] => (level as u32, [a, b, c, d]),
+                    _ => return Err(invalid("expected a level and four children")),
+                };
+                if level == 1 {
+                    let mut states = [0u8; 4];
+                    for (state, child) in states.iter_mut().zip(&children) {
+                        *state = u8::try_from(*child).map_err(|_| invalid("state over 255"))?;
+                    }
+                    nodes.push(Node::States(states));
+                    continue;
+                }
+                if !(2..=MAX_LEVEL).contains(&level) {
+                    return Err(invalid("unsupported level"));
+                }
+                let mut indices = [0; 4];
+                for (slot, child) in indices.iter_mut().zip(&children) {
+                    let child = *child as usize;
+                    if child >= nodes.len() || (child != 0 && nodes[child].level() != level - 1) {
+                        return Err(invalid("child is not an earlier node one level down"));
+                    }
+                    *slot = child;
+                }
+                nodes.push(Node::Children(level, indices));
+            }
+        }
+
+        let root = nodes.len() - 1;
+        Ok(Self::new(rule, generation, nodes, root))
+    }
+}
+
+impl Display for Macrocell {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "[M2] (wasm-game-of-life)")?;
+        if let Some(rule) = &self.rule {
+            writeln!(f, "#R {}", rule)?;
+        }
+        if self.generation != 0 {
+            writeln!(f, "#G {}", self.generation)?;
+        }
+        for node in &self.nodes[1..] {
+            match node {
+                Node::Bits(rows) => {
+                    let last = rows.iter().rposition(|row| *row != 0).unwrap_or(0);
+                    for row in &rows[..=last] {
+                        let width = 8 - row.leading_zeros() as usize;
+                        for x in 0..width {
+                            write!(f, "{}", if row & (1 << x) != 0 { '*' } else { '.' })?;
+                        }
+                        write!(f, "$")?;
+                    }
+                    writeln!(f)?;
+                }
+                Node::States(states) => writeln!(
+                    f,
+                    "1 {} {} {} {}",
+                    states[0], states[1], states[2], states[3]
+                )?,
+                Node::Children(level, children) => writeln!(
+                    f,
+                    "{} {} {} {} {}",
+                    level, children[0], children[1], children[2], children[3]
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A glider in the nw corner of the se quadrant of a level 4 root.
+    const GLIDER: &str = "[M2] (golly 4.2)\n#R B3/S23\n#G 12\n.*$..*$***$\n4 0 0 0 1\n";
+
+    #[test]
+    fn parse_macrocell_test() {
+        let pattern: Macrocell = GLIDER.parse().unwrap();
+        assert_eq!(pattern.rule(), Some("B3/S23"));
+        assert_eq!(pattern.generation(), 12);
+        assert_eq!(pattern.population(), 5);
+        assert_eq!(pattern.bounds(), Some((0, 0, 3, 3)));
+        assert_eq!(pattern.crop(1, 0, 2, 2), vec![(1, 0, 1), (2, 1, 1)]);
+        assert_eq!(pattern.to_string().lines().nth(3), Some(".*$..*$***$"));
+
+        assert_eq!(
+            "x = 3".parse::<Macrocell>(),
+            Err(MacrocellError::MissingHeader)
+        );
+        assert!("[M2]\n.*$\n4 0 0 0 2\n".parse::<Macrocell>().is_err());
+    }
+
+    #[test]
+    fn write_macrocell_test() {
+        let cells = [(-40, -3, 1), (0, 0, 1), (1, 0, 1), (1000, 7, 1)];
+        let pattern = Macrocell::from_cells(&cells, Some("B3/S23".to_string()), 0);
+        let text = pattern.to_string();
+        let parsed: Macrocell = text.parse().unwrap();
+        assert_eq!(parsed, pattern);
+        assert_eq!(parsed.bounds(), Some((-40, -3, 1041, 11)));
+        let mut cropped = parsed.crop(-100, -100, 2000, 2000);
+        cropped.sort();
+        assert_eq!(cropped, cells.to_vec());
+
+        let states = [(0, 0, 1), (1, 1, 3)];
+        let parsed: Macrocell = Macrocell::from_cells(&states, None, 5)
+            .to_string()
+            .parse()
+            .unwrap();
+        assert_eq!(parsed.crop(0, 0, 2, 2), states.to_vec());
+        assert_eq!(parsed.generation(), 5);
+    }
+
+    #[test]
+    fn shared_subtrees_test() {
+        // 2^40 wide, with one block in each corner: a handful of nodes.
+        let far = (1i64 << 39) - 2;
+        let mut cells = vec![];
+        for (x, y) in [(-far, -far), (far, -far), (-far, far), (far, far)].iter() {
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)].iter() {
+                cells.push((x + dx, y + dy, 1));
+            }
+        }
+        let pattern = Macrocell::from_cells(&cells, None, 0);
+        assert!(pattern.nodes.len() < 200);
+        assert_eq!(pattern.population(), 16);
+        assert_eq!(pattern.bounds().unwrap().2, 2 * far as u64 + 2);
+        assert_eq!(pattern.crop(-far, -far, 4, 4).len(), 4);
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseMargolusError(String);
+
+impl Display for ParseMargolusError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid Margolus rule: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseMargolusError {}
+
+#[cfg(feature = "wasm")]
+impl From<ParseMargolusError> for JsValue {
+    fn from(error: ParseMargolusError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MargolusPreset {
+    BilliardBall,
+    Critters,
+}
+
+impl MargolusPreset {
+    pub fn rule(&self) -> &'static str {
+        match self {
+            MargolusPreset::BilliardBall => "MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15",
+            MargolusPreset::Critters => "MS,D15;14;13;3;11;5;6;1;7;9;10;2;12;4;8;0",
+        }
+    }
+}
+
+/// Block rule for the Margolus neighbourhood. A 2x2 block is read as a number
+/// with the upper left cell worth 1, upper right 2, lower left 4 and lower
+/// right 8, and replaced by `table[block]`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MargolusRule {
+    table: [u8; 16],
+    inverse: Option<[u8; 16]>,
+}
+
+impl MargolusRule {
+    pub fn new(table: [u8; 16]) -> Self {
+        let mut inverse = [0u8; 16];
+        let mut seen = [false; 16];
+        for (block, next) in table.iter().enumerate() {
+            let next = (*next & 0xf) as usize;
+            seen[next] = true;
+            inverse[next] = block as u8;
+        }
+
+        Self {
+            table,
+            inverse: if seen.iter().all(|seen| *seen) {
+                Some(inverse)
+            } else {
+                None
+            },
+        }
+    }
+
+    pub fn is_reversible(&self) -> bool {
+        self.inverse.is_some()
+    }
+
+    pub fn table(&self) -> &[u8; 16] {
+        &self.table
+    }
+
+    pub fn inverse(&self) -> Option<&[u8; 16]> {
+        self.inverse.as_ref()
+    }
+}
+
+impl FromStr for MargolusRule {
+    type Err = ParseMargolusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let entries = s
+            .trim()
+            .strip_prefix("MS,D")
+            .ok_or_else(|| ParseMargolusError(format!("`{}` does not start with MS,D", s)))?;
+        let values = entries
+            .split(';')
+            .map(|entry| match entry.trim().parse::<u8>() {
+                Ok(value) if value < 16 => Ok(value),
+                _ => Err(ParseMargolusError(format!(
+                    "`{}` is not a block 0-15",
+                    entry
+                ))),
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+
+        let mut table = [0u8; 16];
+        if values.len() != table.len() {
+            return Err(ParseMargolusError(format!(
+                "expected 16 entries, found {}",
+                values.len()
+            )));
+        }
+        table.copy_from_slice(&values);
+        Ok(MargolusRule::new(table))
+    }
+}
+
+impl Display for MargolusRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let entries: Vec<String> = self.table.iter().map(|value| value.to_string()).collect();
+        write!(f, "MS,D{}", entries.join(";"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rule_test() {
+        let rule: MargolusRule = MargolusPreset::BilliardBall.rule().parse().unwrap();
+        assert_eq!(rule.table()[1], 8);
+        assert!(rule.is_reversible());
+        assert_eq!(rule.to_string(), MargolusPreset::BilliardBall.rule());
+        assert!("MS,D0;1;2".parse::<MargolusRule>().is_err());
+        assert!("B3/S23".parse::<MargolusRule>().is_err());
+    }
+
+    #[test]
+    fn irreversible_rule_test() {
+        let rule: MargolusRule = "MS,D0;0;0;0;0;0;0;0;0;0;0;0;0;0;0;15".parse().unwrap();
+        assert!(!rule.is_reversible());
+    }
+}
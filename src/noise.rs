@@ -0,0 +1,21 @@
+/// Optional randomness applied while stepping. Every birth or death the rule
+/// asks for only happens with `transition_probability`, and after each
+/// generation every cell is flipped with `flip_rate`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Noise {
+    pub transition_probability: f64,
+    pub flip_rate: f64,
+    pub suppressed: usize,
+    pub flipped: usize,
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Self {
+            transition_probability: 1.0,
+            flip_rate: 0.0,
+            suppressed: 0,
+            flipped: 0,
+        }
+    }
+}
@@ -1,9 +1,11 @@
 use strum::{Display, EnumCount, IntoEnumIterator};
 use strum_macros::{EnumCount as EnumCountMacro, EnumIter};
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[derive(Copy, Clone, Display, EnumIter, EnumCountMacro)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Pattern {
     Blinker,
     Block,
@@ -45,7 +47,7 @@ impl Pattern {
     }
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub fn get_patterns_as_string() -> String {
     let patterns: Vec<String> = Pattern::iter().map(|pattern| pattern.to_string()).collect();
     patterns.join(",")
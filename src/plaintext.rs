@@ -0,0 +1,52 @@
+/// Live cells of a plaintext (`.cells`) pattern: `O` is alive, `.` is dead
+/// and lines starting with `!` are comments.
+pub fn parse_plaintext(text: &str) -> Vec<(usize, usize)> {
+    text.lines()
+        .filter(|line| !line.starts_with('!'))
+        .enumerate()
+        .flat_map(|(y, line)| {
+            line.chars()
+                .enumerate()
+                .filter(|(_, c)| *c == 'O' || *c == '*')
+                .map(move |(x, _)| (x, y))
+        })
+        .collect()
+}
+
+/// Encodes live cells as plaintext, cropped to their bounding box.
+pub fn write_plaintext(cells: &[(usize, usize)], name: &str) -> String {
+    let left = cells.iter().map(|cell| cell.0).min().unwrap_or(0);
+    let top = cells.iter().map(|cell| cell.1).min().unwrap_or(0);
+    let height = cells.iter().map(|cell| cell.1 - top + 1).max().unwrap_or(0);
+
+    let mut rows = vec![String::new(); height];
+    for (x, y) in cells {
+        let row = &mut rows[y - top];
+        while row.len() <= x - left {
+            row.push('.');
+        }
+        row.replace_range(x - left..x - left + 1, "O");
+    }
+
+    let mut out = format!("!Name: {}\n", name);
+    for row in rows {
+        out.push_str(&row);
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plaintext_round_trip_test() {
+        let glider = parse_plaintext("!Name: Glider\n.O\n..O\nOOO\n");
+        assert_eq!(glider, vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        assert_eq!(
+            write_plaintext(&glider, "Glider"),
+            "!Name: Glider\n.O\n..O\nOOO\n"
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use std::fmt::{Display, Formatter};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseRleError {
+    InvalidCount(String),
+    TooLarge(usize, usize),
+}
+
+impl Display for ParseRleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseRleError::InvalidCount(count) => write!(f, "invalid run count {}", count),
+            ParseRleError::TooLarge(width, height) => {
+                write!(f, "pattern does not fit in {}x{}", width, height)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseRleError {}
+
+#[cfg(feature = "wasm")]
+impl From<ParseRleError> for JsValue {
+    fn from(error: ParseRleError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+/// Offsets of the live cells in an RLE pattern, relative to its top left
+/// corner. Header (`x = ...`) and comment (`#`) lines are skipped. Live
+/// cells must fit in `bounds`, so a huge run count can't exhaust memory.
+pub fn parse_rle(rle: &str, bounds: (usize, usize)) -> Result<Vec<(usize, usize)>, ParseRleError> {
+    let mut cells = vec![];
+    let mut cur_x: usize = 0;
+    let mut cur_y: usize = 0;
+    let mut count = String::new();
+    let body = rle.lines().filter(|line| {
+        let line = line.trim_start();
+        !line.starts_with('#') && !line.starts_with('x')
+    });
+    for rule in body.flat_map(|line| line.chars()) {
+        let run = match rule {
+            'b' | '.' | 'o' | '$' if count.is_empty() => 1,
+            'b' | '.' | 'o' | '$' => count
+                .parse::<usize>()
+                .map_err(|_| ParseRleError::InvalidCount(count.clone()))?,
+            _ => 0,
+        };
+        match rule {
+            'b' | '.' => {
+                cur_x = cur_x.saturating_add(run);
+                count.clear();
+            }
+            'o' => {
+                if cur_y >= bounds.1 || cur_x.saturating_add(run) > bounds.0 {
+                    return Err(ParseRleError::TooLarge(bounds.0, bounds.1));
+                }
+                for i in 0..run {
+                    cells.push((cur_x + i, cur_y));
+                }
+                cur_x += run;
+                count.clear();
+            }
+            '$' => {
+                cur_y = cur_y.saturating_add(run);
+                cur_x = 0;
+                count.clear();
+            }
+            '!' => break,
+            '0'..='9' => count.push(rule),
+            _ => {}
+        }
+    }
+
+    Ok(cells)
+}
+
+/// The rule from an RLE header line such as `x = 3, y = 3, rule = B3/S23`.
+pub fn parse_rle_rule(rle: &str) -> Option<String> {
+    let header = rle
+        .lines()
+        .map(str::trim_start)
+        .find(|line| line.starts_with('x'))?;
+    header
+        .split(',')
+        .filter_map(|field| field.split_once('='))
+        .find(|(key, _)| key.trim() == "rule")
+        .map(|(_, rule)| rule.trim().to_string())
+}
+
+/// Encodes live cells as RLE, cropped to their bounding box, with body
+/// lines kept under 70 characters.
+pub fn write_rle(cells: &[(usize, usize)], rule: &str) -> String {
+    let left = cells.iter().map(|cell| cell.0).min().unwrap_or(0);
+    let top = cells.iter().map(|cell| cell.1).min().unwrap_or(0);
+    let width = cells
+        .iter()
+        .map(|cell| cell.0 - left + 1)
+        .max()
+        .unwrap_or(0);
+    let height = cells.iter().map(|cell| cell.1 - top + 1).max().unwrap_or(0);
+
+    let mut rows = vec![vec![false; width]; height];
+    for (x, y) in cells {
+        rows[y - top][x - left] = true;
+    }
+
+    // Runs of (count, tag), with trailing dead cells and blank rows folded
+    // into the `$` runs.
+    let mut runs: Vec<(usize, char)> = vec![];
+    let push = |runs: &mut Vec<(usize, char)>, count: usize, tag: char| match runs.last_mut() {
+        Some((last, last_tag)) if *last_tag == tag => *last += count,
+        _ => runs.push((count, tag)),
+    };
+    for (y, row) in rows.iter().enumerate() {
+        if y > 0 {
+            push(&mut runs, 1, '$');
+        }
+        let end = row.iter().rposition(|alive| *alive).map_or(0, |x| x + 1);
+        for alive in &row[..end] {
+            push(&mut runs, 1, if *alive { 'o' } else { 'b' });
+        }
+    }
+
+    let mut out = format!("x = {}, y = {}, rule = {}\n", width, height, rule);
+    let mut line = String::new();
+    let tokens = runs.iter().map(|(count, tag)| match count {
+        1 => tag.to_string(),
+        _ => format!("{}{}", count, tag),
+    });
+    for token in tokens.chain(std::iter::once("!".to_string())) {
+        if line.len() + token.len() > 70 {
+            out.push_str(&line);
+            out.push('\n');
+            line.clear();
+        }
+        line.push_str(&token);
+    }
+    out.push_str(&line);
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: (usize, usize) = (100, 100);
+
+    #[test]
+    fn parse_glider_test() {
+        let cells = parse_rle("x = 3, y = 3, rule = B3/S23\nbob$2bo$3o!", BOUNDS).unwrap();
+        assert_eq!(cells, vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn parse_blank_rows_test() {
+        let cells = parse_rle("#C two rows apart\no2$3bo!", BOUNDS).unwrap();
+        assert_eq!(cells, vec![(0, 0), (3, 2)]);
+    }
+
+    #[test]
+    fn parse_errors_test() {
+        assert_eq!(
+            parse_rle("999999999o!", BOUNDS),
+            Err(ParseRleError::TooLarge(100, 100))
+        );
+        assert_eq!(
+            parse_rle("100$o!", BOUNDS),
+            Err(ParseRleError::TooLarge(100, 100))
+        );
+        assert_eq!(
+            parse_rle("99999999999999999999999o!", BOUNDS),
+            Err(ParseRleError::InvalidCount(
+                "99999999999999999999999".to_string()
+            ))
+        );
+        // Long dead runs are fine.
+        assert_eq!(parse_rle("999999999b$o!", BOUNDS), Ok(vec![(0, 1)]));
+    }
+
+    #[test]
+    fn write_rle_round_trip_test() {
+        let glider = vec![(11, 10), (12, 11), (10, 12), (11, 12), (12, 12)];
+        let rle = write_rle(&glider, "B3/S23");
+        assert_eq!(rle, "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n");
+        assert_eq!(parse_rle_rule(&rle).as_deref(), Some("B3/S23"));
+
+        let sparse = vec![(0, 0), (3, 2)];
+        assert_eq!(parse_rle(&write_rle(&sparse, "B3/S23"), BOUNDS), Ok(sparse));
+    }
+}
@@ -0,0 +1,91 @@
+use std::fmt::{Display, Formatter};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::snapshot::SnapshotError;
+
+/// Longest share string we hand out, so links stay usable in chat and
+/// browsers.
+pub const MAX_SHARE_LENGTH: usize = 8000;
+// Guards against small strings that inflate to huge buffers.
+const MAX_SNAPSHOT_BYTES: usize = 64 << 20;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ShareError {
+    TooLarge(usize),
+    Encoding,
+    Snapshot(SnapshotError),
+}
+
+impl Display for ShareError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShareError::TooLarge(length) => write!(
+                f,
+                "grid needs {} characters to share, the limit is {}",
+                length, MAX_SHARE_LENGTH
+            ),
+            ShareError::Encoding => write!(f, "not a share string"),
+            ShareError::Snapshot(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ShareError {}
+
+impl From<SnapshotError> for ShareError {
+    fn from(error: SnapshotError) -> Self {
+        ShareError::Snapshot(error)
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl From<ShareError> for JsValue {
+    fn from(error: ShareError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+/// Deflates snapshot bytes and encodes them as unpadded base64url.
+pub fn encode(snapshot: &[u8]) -> Result<String, ShareError> {
+    let text = URL_SAFE_NO_PAD.encode(miniz_oxide::deflate::compress_to_vec(snapshot, 9));
+    if text.len() > MAX_SHARE_LENGTH {
+        return Err(ShareError::TooLarge(text.len()));
+    }
+    Ok(text)
+}
+
+pub fn decode(text: &str) -> Result<Vec<u8>, ShareError> {
+    let text = text.trim();
+    if text.len() > MAX_SHARE_LENGTH {
+        return Err(ShareError::TooLarge(text.len()));
+    }
+    let deflated = URL_SAFE_NO_PAD
+        .decode(text)
+        .map_err(|_| ShareError::Encoding)?;
+    miniz_oxide::inflate::decompress_to_vec_with_limit(&deflated, MAX_SNAPSHOT_BYTES)
+        .map_err(|_| ShareError::Encoding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_test() {
+        let bytes: Vec<u8> = (0..200).map(|i| (i % 7) as u8).collect();
+        let text = encode(&bytes).unwrap();
+        assert!(text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(decode(&text).unwrap(), bytes);
+        assert_eq!(decode("not base64!"), Err(ShareError::Encoding));
+        assert_eq!(
+            decode(&"A".repeat(MAX_SHARE_LENGTH + 1)),
+            Err(ShareError::TooLarge(MAX_SHARE_LENGTH + 1))
+        );
+    }
+}
@@ -0,0 +1,408 @@
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::backend::Backend;
+use crate::life_rule::{LifeRule, ParseRuleError};
+use crate::update_schedule::UpdateSchedule;
+use crate::variant::Variant;
+
+const MAGIC: &[u8; 4] = b"GOLS";
+/// Only bumped for changes older readers cannot skip over. New data goes in
+/// new sections, or at the end of an existing one.
+pub const VERSION: u8 = 1;
+/// Largest grid a snapshot may declare, checked before anything is
+/// allocated for it.
+pub const MAX_CELLS: usize = 1 << 24;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SnapshotError {
+    NotASnapshot,
+    UnsupportedVersion(u8),
+    Truncated,
+    MissingSection(&'static str),
+    Invalid(String),
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::NotASnapshot => write!(f, "not a snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "snapshot version {} is newer than {}", version, VERSION)
+            }
+            SnapshotError::Truncated => write!(f, "snapshot is truncated"),
+            SnapshotError::MissingSection(tag) => write!(f, "snapshot has no {} section", tag),
+            SnapshotError::Invalid(message) => write!(f, "invalid snapshot: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+#[cfg(feature = "wasm")]
+impl From<SnapshotError> for JsValue {
+    fn from(error: SnapshotError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+/// Everything `Grid::save_snapshot` keeps. `states` are row-major, 0 for
+/// dead and colour + 1 for live cells.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    pub width: usize,
+    pub height: usize,
+    pub generation: usize,
+    pub rule: LifeRule,
+    pub variant: Variant,
+    pub brush: u8,
+    pub schedule: UpdateSchedule,
+    pub alpha: f64,
+    pub transition_probability: f64,
+    pub flip_rate: f64,
+    pub backend: Backend,
+    pub incremental: bool,
+    pub rng: (u64, u64),
+    pub margolus: Option<String>,
+    pub rule_file: Option<String>,
+    pub states: Vec<u8>,
+}
+
+/// The layout is `GOLS`, a version byte and then sections of a four letter
+/// tag, a varint length and the payload. Readers skip tags they don't know
+/// and bytes past the end of the fields they do.
+impl Snapshot {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+
+        let mut head = vec![];
+        put_varint(&mut head, self.width as u64);
+        put_varint(&mut head, self.height as u64);
+        put_varint(&mut head, self.generation as u64);
+        put_section(&mut bytes, b"HEAD", &head);
+
+        put_section(&mut bytes, b"RULE", self.rule.to_string().as_bytes());
+
+        let mut settings = vec![
+            self.variant as u8,
+            self.brush,
+            self.schedule as u8,
+            self.backend as u8,
+            self.incremental as u8,
+        ];
+        settings.extend_from_slice(&self.alpha.to_le_bytes());
+        settings.extend_from_slice(&self.transition_probability.to_le_bytes());
+        settings.extend_from_slice(&self.flip_rate.to_le_bytes());
+        put_section(&mut bytes, b"SETT", &settings);
+
+        let mut rng = self.rng.0.to_le_bytes().to_vec();
+        rng.extend_from_slice(&self.rng.1.to_le_bytes());
+        put_section(&mut bytes, b"RAND", &rng);
+
+        if let Some(rule) = &self.margolus {
+            put_section(&mut bytes, b"MARG", rule.as_bytes());
+        }
+        if let Some(text) = &self.rule_file {
+            put_section(&mut bytes, b"RULF", text.as_bytes());
+        }
+
+        // Alternating dead and live run lengths, starting with dead.
+        let mut live = vec![];
+        for (alive, length) in runs(self.states.iter().map(|state| *state != 0)) {
+            if live.is_empty() && alive {
+                put_varint(&mut live, 0);
+            }
+            put_varint(&mut live, length as u64);
+        }
+        put_section(&mut bytes, b"LIVE", &live);
+
+        // Only written when some cell isn't colour 0: run length, state.
+        if self.states.iter().any(|state| *state > 1) {
+            let mut colours = vec![];
+            for (state, length) in runs(self.states.iter().copied()) {
+                put_varint(&mut colours, length as u64);
+                colours.push(state);
+            }
+            put_section(&mut bytes, b"COLR", &colours);
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let mut reader = Reader::new(bytes);
+        if reader.take(4).ok() != Some(&MAGIC[..]) {
+            return Err(SnapshotError::NotASnapshot);
+        }
+        let version = reader.byte()?;
+        if version > VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let mut snapshot = None;
+        let mut live = None;
+        let mut colours = None;
+        let mut sections = vec![];
+        while !reader.is_empty() {
+            let tag = reader.take(4)?;
+            let length = usize::try_from(reader.varint()?).map_err(|_| SnapshotError::Truncated)?;
+            let payload = reader.take(length)?;
+            match tag {
+                b"HEAD" => {
+                    let mut head = Reader::new(payload);
+                    let width = head.usize()?;
+                    let height = head.usize()?;
+                    let generation = head.usize()?;
+                    snapshot = Some((width, height, generation));
+                }
+                b"LIVE" => live = Some(payload),
+                b"COLR" => colours = Some(payload),
+                _ => sections.push((tag, payload)),
+            }
+        }
+
+        let (width, height, generation) = snapshot.ok_or(SnapshotError::MissingSection("HEAD"))?;
+        let cells = width
+            .checked_mul(height)
+            .filter(|cells| *cells <= MAX_CELLS)
+            .ok_or_else(|| SnapshotError::Invalid("grid is too large".to_string()))?;
+        let mut snapshot = Snapshot {
+            width,
+            height,
+            generation,
+            rule: LifeRule::default(),
+            variant: Variant::Life,
+            brush: 0,
+            schedule: UpdateSchedule::Synchronous,
+            alpha: 1.0,
+            transition_probability: 1.0,
+            flip_rate: 0.0,
+            backend: Backend::Cells,
+            incremental: true,
+            rng: (0, 0),
+            margolus: None,
+            rule_file: None,
+            states: decode_live(live.ok_or(SnapshotError::MissingSection("LIVE"))?, cells)?,
+        };
+        if let Some(colours) = colours {
+            snapshot.states = decode_colours(colours, cells)?;
+        }
+
+        for (tag, payload) in sections {
+            let mut section = Reader::new(payload);
+            match tag {
+                b"RULE" => {
+                    snapshot.rule = section
+                        .string()?
+                        .parse()
+                        .map_err(|e: ParseRuleError| SnapshotError::Invalid(e.to_string()))?
+                }
+                b"SETT" => {
+                    snapshot.variant = match section.byte()? {
+                        0 => Variant::Life,
+                        1 => Variant::Immigration,
+                        2 => Variant::QuadLife,
+                        other => return Err(invalid("variant", other)),
+                    };
+                    snapshot.brush = section.byte()?;
+                    snapshot.schedule = match section.byte()? {
+                        0 => UpdateSchedule::Synchronous,
+                        1 => UpdateSchedule::RandomOrder,
+                        2 => UpdateSchedule::Sweep,
+                        3 => UpdateSchedule::AlphaAsynchronous,
+                        other => return Err(invalid("schedule", other)),
+                    };
+                    snapshot.backend = match section.byte()? {
+                        0 => Backend::Cells,
+                        1 => Backend::BitParallel,
+                        other => return Err(invalid("backend", other)),
+                    };
+                    snapshot.incremental = section.byte()? != 0;
+                    snapshot.alpha = section.f64()?;
+                    snapshot.transition_probability = section.f64()?;
+                    snapshot.flip_rate = section.f64()?;
+                }
+                b"RAND" => snapshot.rng = (section.u64()?, section.u64()?),
+                b"MARG" => snapshot.margolus = Some(section.string()?),
+                b"RULF" => snapshot.rule_file = Some(section.string()?),
+                _ => {}
+            }
+        }
+        Ok(snapshot)
+    }
+}
+
+fn invalid(field: &str, value: u8) -> SnapshotError {
+    SnapshotError::Invalid(format!("unknown {} {}", field, value))
+}
+
+fn decode_live(payload: &[u8], cells: usize) -> Result<Vec<u8>, SnapshotError> {
+    let mut reader = Reader::new(payload);
+    let mut states = Vec::with_capacity(cells);
+    let mut alive = false;
+    while !reader.is_empty() {
+        let length = reader.usize()?;
+        if length > cells - states.len() {
+            return Err(SnapshotError::Invalid("too many cells".to_string()));
+        }
+        states.resize(states.len() + length, alive as u8);
+        alive = !alive;
+    }
+    states.resize(cells, 0);
+    Ok(states)
+}
+
+fn decode_colours(payload: &[u8], cells: usize) -> Result<Vec<u8>, SnapshotError> {
+    let mut reader = Reader::new(payload);
+    let mut states = Vec::with_capacity(cells);
+    while !reader.is_empty() {
+        let length = reader.usize()?;
+        let state = reader.byte()?;
+        if length > cells - states.len() {
+            return Err(SnapshotError::Invalid("too many cells".to_string()));
+        }
+        states.resize(states.len() + length, state);
+    }
+    states.resize(cells, 0);
+    Ok(states)
+}
+
+// Consecutive equal values and how many there are of each.
+fn runs<T: PartialEq + Copy>(values: impl Iterator<Item = T>) -> Vec<(T, usize)> {
+    let mut runs: Vec<(T, usize)> = vec![];
+    for value in values {
+        match runs.last_mut() {
+            Some((last, length)) if *last == value => *length += 1,
+            _ => runs.push((value, 1)),
+        }
+    }
+    runs
+}
+
+fn put_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn put_section(bytes: &mut Vec<u8>, tag: &[u8; 4], payload: &[u8]) {
+    bytes.extend_from_slice(tag);
+    put_varint(bytes, payload.len() as u64);
+    bytes.extend_from_slice(payload);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], SnapshotError> {
+        if length > self.bytes.len() {
+            return Err(SnapshotError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, SnapshotError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(SnapshotError::Invalid("varint is too long".to_string()))
+    }
+
+    fn usize(&mut self) -> Result<usize, SnapshotError> {
+        usize::try_from(self.varint()?)
+            .map_err(|_| SnapshotError::Invalid("number is too large".to_string()))
+    }
+
+    fn u64(&mut self) -> Result<u64, SnapshotError> {
+        let mut word = [0; 8];
+        word.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(word))
+    }
+
+    fn f64(&mut self) -> Result<f64, SnapshotError> {
+        Ok(f64::from_bits(self.u64()?))
+    }
+
+    // The rest of the section as UTF-8.
+    fn string(&mut self) -> Result<String, SnapshotError> {
+        let bytes = self.take(self.bytes.len())?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| SnapshotError::Invalid("text is not UTF-8".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn varint_test() {
+        for value in [0, 1, 127, 128, 300, u64::MAX].iter() {
+            let mut bytes = vec![];
+            put_varint(&mut bytes, *value);
+            assert_eq!(Reader::new(&bytes).varint(), Ok(*value));
+        }
+        assert_eq!(Reader::new(&[0x80]).varint(), Err(SnapshotError::Truncated));
+    }
+
+    #[test]
+    fn unknown_sections_are_skipped_test() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        put_section(&mut bytes, b"HEAD", &[2, 1, 7]);
+        put_section(&mut bytes, b"XTRA", b"from the future");
+        put_section(&mut bytes, b"LIVE", &[1, 1]);
+        let snapshot = Snapshot::decode(&bytes).unwrap();
+        assert_eq!(snapshot.states, vec![0, 1]);
+        assert_eq!(snapshot.generation, 7);
+
+        bytes[4] = VERSION + 1;
+        assert_eq!(
+            Snapshot::decode(&bytes),
+            Err(SnapshotError::UnsupportedVersion(VERSION + 1))
+        );
+        assert_eq!(Snapshot::decode(b"RLE!"), Err(SnapshotError::NotASnapshot));
+    }
+
+    #[test]
+    fn oversized_grid_test() {
+        let mut head = vec![];
+        put_varint(&mut head, 1 << 32);
+        put_varint(&mut head, 1 << 20);
+        put_varint(&mut head, 0);
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        put_section(&mut bytes, b"HEAD", &head);
+        put_section(&mut bytes, b"LIVE", &[]);
+        assert_eq!(
+            Snapshot::decode(&bytes),
+            Err(SnapshotError::Invalid("grid is too large".to_string()))
+        );
+    }
+}
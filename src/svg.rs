@@ -0,0 +1,208 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::utils;
+
+/// Settings for `Grid::region_to_svg`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SvgStyle {
+    cell_px: usize,
+    grid_lines: bool,
+    label_every: usize,
+    palette: Vec<[u8; 3]>,
+    phases: usize,
+}
+
+impl Default for SvgStyle {
+    fn default() -> Self {
+        SvgStyle {
+            cell_px: 10,
+            grid_lines: false,
+            label_every: 0,
+            palette: utils::DEFAULT_COLOURS.to_vec(),
+            phases: 1,
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl SvgStyle {
+    pub fn new() -> SvgStyle {
+        SvgStyle::default()
+    }
+
+    pub fn set_cell_px(&mut self, cell_px: usize) {
+        self.cell_px = cell_px.max(1);
+    }
+
+    pub fn set_grid_lines(&mut self, grid_lines: bool) {
+        self.grid_lines = grid_lines;
+    }
+
+    /// Labels every `every`th row and column with its grid coordinate. 0
+    /// turns labels off.
+    pub fn set_labels(&mut self, every: usize) {
+        self.label_every = every;
+    }
+
+    /// Flat RGB triples for state 0 (dead) and then each live colour, as in
+    /// `Grid::to_png`.
+    pub fn set_colours(&mut self, colours: &[u8]) {
+        self.palette = utils::palette(colours);
+    }
+
+    /// Draws this many successive generations side by side.
+    pub fn set_phases(&mut self, phases: usize) {
+        self.phases = phases.max(1);
+    }
+
+    pub fn phases(&self) -> usize {
+        self.phases
+    }
+}
+
+impl SvgStyle {
+    fn colour(&self, state: u8) -> String {
+        let [r, g, b] = self.palette[state as usize % self.palette.len()];
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+
+    fn font_px(&self) -> usize {
+        (self.cell_px * 4 / 5).max(8)
+    }
+
+    /// An SVG of `phases`, each `width` x `height` row-major cell states of
+    /// the region whose top left cell is (`left`, `top`).
+    pub(crate) fn render(
+        &self,
+        (left, top): (usize, usize),
+        width: usize,
+        height: usize,
+        phases: &[Vec<u8>],
+    ) -> String {
+        let cell = self.cell_px;
+        let font = self.font_px();
+        let (margin_x, margin_y) = if self.label_every > 0 {
+            let digits = (top + height).max(1).to_string().len();
+            (font * (digits + 1) * 3 / 5, font * 3 / 2)
+        } else {
+            (0, 0)
+        };
+        let gap = if phases.len() > 1 { 2 * cell } else { 0 };
+        let phase_px = width * cell + gap;
+        let total_width = margin_x + phase_px * phases.len() - gap;
+        let total_height = margin_y + height * cell;
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+             viewBox=\"0 0 {w} {h}\" shape-rendering=\"crispEdges\">\n",
+            w = total_width,
+            h = total_height
+        );
+        for (phase, states) in phases.iter().enumerate() {
+            let x0 = margin_x + phase * phase_px;
+            svg.push_str(&format!(
+                "<g transform=\"translate({} {})\">\n<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                x0,
+                margin_y,
+                width * cell,
+                height * cell,
+                self.colour(0)
+            ));
+            for (state, runs) in runs(width, states) {
+                svg.push_str(&format!("<g fill=\"{}\">\n", self.colour(state)));
+                for (x, y, length) in runs {
+                    svg.push_str(&format!(
+                        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+                        x * cell,
+                        y * cell,
+                        length * cell,
+                        cell
+                    ));
+                }
+                svg.push_str("</g>\n");
+            }
+            if self.grid_lines {
+                let mut path = String::new();
+                for x in 0..=width {
+                    path.push_str(&format!("M{} 0V{}", x * cell, height * cell));
+                }
+                for y in 0..=height {
+                    path.push_str(&format!("M0 {}H{}", y * cell, width * cell));
+                }
+                svg.push_str(&format!(
+                    "<path d=\"{}\" fill=\"none\" stroke=\"#808080\" stroke-width=\"{}\"/>\n",
+                    path,
+                    (cell as f64 / 10.0).clamp(0.5, 1.0)
+                ));
+            }
+            svg.push_str("</g>\n");
+
+            if self.label_every > 0 {
+                let style = format!("font-family=\"monospace\" font-size=\"{}\"", font);
+                for x in (0..width).filter(|x| (left + x) % self.label_every == 0) {
+                    svg.push_str(&format!(
+                        "<text x=\"{}\" y=\"{}\" {} text-anchor=\"middle\">{}</text>\n",
+                        x0 + x * cell + cell / 2,
+                        font,
+                        style,
+                        left + x
+                    ));
+                }
+                if phase == 0 {
+                    for y in (0..height).filter(|y| (top + y) % self.label_every == 0) {
+                        svg.push_str(&format!(
+                            "<text x=\"{}\" y=\"{}\" {} text-anchor=\"end\" \
+                             dominant-baseline=\"middle\">{}</text>\n",
+                            margin_x - font / 2,
+                            margin_y + y * cell + cell / 2,
+                            style,
+                            top + y
+                        ));
+                    }
+                }
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+// Horizontal runs of one state, as `(x, y, length)`.
+type Runs = Vec<(usize, usize, usize)>;
+
+// Runs of equal live states, grouped by state.
+fn runs(width: usize, states: &[u8]) -> Vec<(u8, Runs)> {
+    let mut by_state: Vec<(u8, Runs)> = vec![];
+    for (y, row) in states.chunks(width.max(1)).enumerate() {
+        let mut x = 0;
+        while x < row.len() {
+            let state = row[x];
+            let length = row[x..].iter().take_while(|s| **s == state).count();
+            if state != 0 {
+                match by_state.iter_mut().find(|(s, _)| *s == state) {
+                    Some((_, runs)) => runs.push((x, y, length)),
+                    None => by_state.push((state, vec![(x, y, length)])),
+                }
+            }
+            x += length;
+        }
+    }
+    by_state.sort_by_key(|(state, _)| *state);
+    by_state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_test() {
+        let states = [1, 1, 0, 2, 2, 2, 0, 1, 1];
+        assert_eq!(
+            runs(3, &states),
+            vec![(1, vec![(0, 0, 2), (1, 2, 2)]), (2, vec![(0, 1, 3)])]
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use std::ops::Range;
+
+use crate::cell::Cell;
+
+pub const TILE_SIZE: usize = 32;
+
+/// Bookkeeping for incremental stepping. The grid is cut into
+/// `TILE_SIZE` squares and for each one we remember whether it changed in
+/// the last generation and whether it differs from two generations ago.
+/// `back` holds the generation before the current one and is reused as the
+/// target of the next step.
+#[derive(Clone, Debug)]
+pub struct Tiles {
+    columns: usize,
+    rows: usize,
+    changed: Vec<bool>,
+    changed_since_two_ago: Vec<bool>,
+    pub back: Vec<Vec<Cell>>,
+    valid: bool,
+}
+
+// Only a cache, two grids with the same cells are equal whatever it holds.
+impl PartialEq for Tiles {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Tiles {
+    pub fn new(size: (usize, usize)) -> Self {
+        let columns = size.0.div_ceil(TILE_SIZE);
+        let rows = size.1.div_ceil(TILE_SIZE);
+        Self {
+            columns,
+            rows,
+            changed: vec![true; columns * rows],
+            changed_since_two_ago: vec![true; columns * rows],
+            back: vec![],
+            valid: false,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    /// The flags no longer describe the cells, e.g. after an edit or a rule
+    /// change. The next incremental step recomputes every tile.
+    pub fn invalidate(&mut self) {
+        self.valid = false;
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+
+    pub fn update(&mut self, changed: Vec<bool>, changed_since_two_ago: Vec<bool>) {
+        self.changed = changed;
+        self.changed_since_two_ago = changed_since_two_ago;
+        self.valid = true;
+    }
+
+    pub fn changed(&self, tile: usize) -> bool {
+        self.changed[tile]
+    }
+
+    pub fn active(&self) -> usize {
+        self.changed.iter().filter(|changed| **changed).count()
+    }
+
+    pub fn bounds(&self, tile: usize, size: (usize, usize)) -> (Range<usize>, Range<usize>) {
+        let x = tile % self.columns * TILE_SIZE;
+        let y = tile / self.columns * TILE_SIZE;
+        (
+            x..(x + TILE_SIZE).min(size.0),
+            y..(y + TILE_SIZE).min(size.1),
+        )
+    }
+
+    /// Whether the tile or one of its eight neighbours changed last
+    /// generation. If not, the tile stays as it is.
+    pub fn neighbourhood_changed(&self, tile: usize) -> bool {
+        self.neighbourhood(tile).any(|t| self.changed[t])
+    }
+
+    /// Whether the tile or one of its neighbours differs from two
+    /// generations ago. If not, the tile is oscillating with period 2 and
+    /// the next generation is the one in `back`.
+    pub fn neighbourhood_changed_since_two_ago(&self, tile: usize) -> bool {
+        self.neighbourhood(tile)
+            .any(|t| self.changed_since_two_ago[t])
+    }
+
+    fn neighbourhood(&self, tile: usize) -> impl Iterator<Item = usize> + '_ {
+        let (column, row) = (tile % self.columns, tile / self.columns);
+        let columns = column.saturating_sub(1)..(column + 2).min(self.columns);
+        let rows = row.saturating_sub(1)..(row + 2).min(self.rows);
+        rows.flat_map(move |r| columns.clone().map(move |c| r * self.columns + c))
+    }
+}
@@ -0,0 +1,18 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UpdateSchedule {
+    /// Every cell is updated at once from the previous generation.
+    Synchronous,
+    /// Cells are updated one at a time, in place, in a new random order every
+    /// generation.
+    RandomOrder,
+    /// Cells are updated one at a time, in place, row by row.
+    Sweep,
+    /// Every cell is updated from the previous generation with probability
+    /// alpha and otherwise keeps its state.
+    AlphaAsynchronous,
+}
@@ -1,3 +1,4 @@
+#[allow(dead_code)]
 pub fn set_panic_hook() {
     // When the `console_error_panic_hook` feature is enabled, we can call the
     // `set_panic_hook` function at least once during initialization, and then
@@ -8,3 +9,40 @@ pub fn set_panic_hook() {
     #[cfg(feature = "console_error_panic_hook")]
     console_error_panic_hook::set_once();
 }
+
+/// Default palette for images and SVGs: dead cells, then one entry per
+/// colour, as in `www/index.js`.
+pub const DEFAULT_COLOURS: [[u8; 3]; 5] = [
+    [0x00, 0x00, 0x00],
+    [0x00, 0x8f, 0x11],
+    [0xc5, 0x1b, 0x1b],
+    [0x1b, 0x6f, 0xc5],
+    [0xc5, 0xb3, 0x1b],
+];
+
+/// Splits flat RGB triples into a palette, falling back to
+/// `DEFAULT_COLOURS` when fewer than two entries are given.
+pub fn palette(colours: &[u8]) -> Vec<[u8; 3]> {
+    if colours.len() < 6 {
+        return DEFAULT_COLOURS.to_vec();
+    }
+    colours
+        .chunks_exact(3)
+        .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+        .collect()
+}
+
+/// Milliseconds from some fixed point, for measuring time budgets.
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+pub fn now_ms() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+pub fn now_ms() -> f64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64() * 1000.0)
+}
@@ -0,0 +1,21 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Variant {
+    Life,
+    Immigration,
+    QuadLife,
+}
+
+impl Variant {
+    pub fn colours(&self) -> u8 {
+        match self {
+            Variant::Life => 1,
+            Variant::Immigration => 2,
+            Variant::QuadLife => 4,
+        }
+    }
+}
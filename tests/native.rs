@@ -0,0 +1,19 @@
+//! The library used from plain Rust, without the JS bindings.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use wasm_game_of_life::{Grid, GridError, Pattern};
+
+#[test]
+fn native_api() {
+    let mut grid = Grid::new(10, 10);
+    grid.spawn_pattern(Pattern::Block, 2, 2, 0);
+    grid.set_alive(8, 8, 0).unwrap();
+    assert_eq!(grid.set_alive(-1, 3, 0), Err(GridError::OutOfBounds(-1, 3)));
+    assert!(grid.cell(10, 0).is_none());
+
+    grid.step_forward();
+    let live: Vec<(i64, i64)> = grid.live().collect();
+    assert_eq!(live.len(), 4);
+    assert!(live.iter().all(|(x, y)| grid.cell(*x, *y).is_some()));
+}
@@ -1,16 +1,32 @@
-import {get_patterns_as_string, Grid} from "wasm-game-of-life";
+import {Animation, AnimationFormat, get_patterns_as_string, Grid, Lenia, LeniaCreature, Variant} from "wasm-game-of-life";
 import {memory} from "wasm-game-of-life/wasm_game_of_life_bg.wasm";
 
 const CELL_SIZE = 5; // px
 const GRID_COLOR = "#003b00";
 const DEAD_COLOR = "#000000";
-const ALIVE_COLOR = "#008f11";
+const ALIVE_COLORS = ["#008f11", "#c51b1b", "#1b6fc5", "#c5b31b"];
+let palette = ALIVE_COLORS;
 
 
-const width = 200;
-const height = 200;
+// A board shared with "Share link" arrives in the URL fragment.
+function sharedGrid() {
+    const shared = window.location.hash.slice(1);
+    if (!shared) {
+        return null;
+    }
+    try {
+        return Grid.from_share_string(shared);
+    } catch (error) {
+        console.error(error);
+        return null;
+    }
+}
 
-const grid = Grid.new(width, height);
+const shared = sharedGrid();
+const grid = shared || Grid.new(200, 200);
+const width = grid.width();
+const height = grid.height();
+grid.set_record_changes(true);
 
 const canvas = document.getElementById("game-of-life-canvas");
 const cursorSpawn = document.getElementById("cursorSpawn");
@@ -18,19 +34,36 @@ const cursorSpawn = document.getElementById("cursorSpawn");
 canvas.height = (CELL_SIZE + 1) * height + 1;
 canvas.width = (CELL_SIZE + 1) * width + 1;
 
-grid.spawn_glider_gun(75, 15);
-grid.spawn_pulsar(10, 35);
-grid.spawn_pentadecanthlon(25, 55);
-grid.spawn_acorn(25, 175);
-grid.spawn_r_pentomino(180, 25);
-grid.spawn_glider_loop(100, 100);
-grid.spawn_pulsar(100, 100);
-grid.spawn_infinite_growth_1(175, 175);
+if (!shared) {
+    grid.spawn_glider_gun(75, 15);
+    grid.spawn_pulsar(10, 35);
+    grid.spawn_pentadecanthlon(25, 55);
+    grid.spawn_acorn(25, 175);
+    grid.spawn_r_pentomino(180, 25);
+    grid.spawn_glider_loop(100, 100);
+    grid.spawn_pulsar(100, 100);
+    grid.spawn_infinite_growth_1(175, 175);
+}
 
 const ctx = canvas.getContext("2d");
 
+const LENIA_CELL_SIZE = 3; // px
+const leniaWidth = 128;
+const leniaHeight = 128;
+
+const lenia = Lenia.new(leniaWidth, leniaHeight);
+lenia.spawn_creature(LeniaCreature.Orbium, 40, 40);
+
+const leniaCanvas = document.getElementById("lenia-canvas");
+leniaCanvas.height = LENIA_CELL_SIZE * leniaHeight;
+leniaCanvas.width = LENIA_CELL_SIZE * leniaWidth;
+const leniaCtx = leniaCanvas.getContext("2d");
+
 let animationIds = [];
+// Set whenever the grid is edited outside of step_forward.
+let fullRedraw = true;
 let delayMs = 200;
+let fastForwardMs = 0;
 let paused = false;
 
 const delay = async (ms) => {
@@ -40,10 +73,23 @@ const delay = async (ms) => {
 const renderLoop = async () => {
     if (!paused) {
         await delay(delayMs);
-        drawGrid();
-        drawCells();
+        if (fullRedraw) {
+            drawGrid();
+            drawCells();
+            fullRedraw = false;
+        }
+        drawLenia();
 
-        grid.step_forward();
+        if (fastForwardMs > 0) {
+            // Many generations per frame, so the change list only covers
+            // the last one.
+            grid.step_for_ms(fastForwardMs);
+            fullRedraw = true;
+        } else {
+            grid.step_forward();
+            drawChanges();
+        }
+        lenia.step_forward();
         const animationId = requestAnimationFrame(renderLoop);
         animationIds.push(animationId);
     }
@@ -53,26 +99,130 @@ const rleSpawn = () => {
     const x = document.getElementById("rleX").value;
     const y = document.getElementById("rleY").value;
     const rle = document.getElementById("rle").value;
-    grid.rle_spawn(x, y, rle);
+    try {
+        grid.rle_spawn(x, y, rle, selectedColour());
+    } catch (error) {
+        alert(error);
+        return;
+    }
+    fullRedraw = true;
 }
 
 const spawn = () => {
     const x = document.getElementById("xSpawn").value
     const y = document.getElementById("ySpawn").value
     const pattern = document.getElementById("spawnSelection").value;
-    grid.spawn_pattern(pattern, x, y);
+    grid.spawn_pattern(pattern, x, y, selectedColour());
+    fullRedraw = true;
+};
+
+const selectedColour = () => {
+    return Number(document.getElementById("colour").value);
 };
 
+function loadRuleFile() {
+    const text = document.getElementById("ruleFile").value;
+    try {
+        grid.load_rule_file(text);
+    } catch (error) {
+        alert(error);
+        return;
+    }
+    fullRedraw = true;
+
+    const rgb = grid.rule_file_colours();
+    palette = ALIVE_COLORS;
+    if (rgb.length > 0) {
+        // Entry 0 is the dead state, live cells store state - 1.
+        palette = [];
+        for (let i = 3; i < rgb.length; i += 3) {
+            palette.push(`rgb(${rgb[i]}, ${rgb[i + 1]}, ${rgb[i + 2]})`);
+        }
+    }
+}
+
+function savePng() {
+    const bytes = grid.to_png(CELL_SIZE, new Uint8Array());
+    const url = URL.createObjectURL(new Blob([bytes], {type: "image/png"}));
+    const link = document.createElement("a");
+    link.href = url;
+    link.download = `generation-${grid.generation()}.png`;
+    link.click();
+    URL.revokeObjectURL(url);
+}
+
+async function shareLink() {
+    let text;
+    try {
+        text = grid.to_share_string();
+    } catch (error) {
+        alert(error);
+        return;
+    }
+    const url = new URL(window.location.href);
+    url.hash = text;
+    history.replaceState(null, "", url);
+    await navigator.clipboard.writeText(url.toString());
+}
+
+// Runs the grid on for the given number of generations and downloads them.
+function recordGif() {
+    const generations = parseInt(document.getElementById("recordGenerations").value, 10) || 100;
+    const animation = Animation.new(AnimationFormat.Gif);
+    animation.set_cell_px(2);
+    const start = grid.generation();
+    const bytes = grid.record_animation(generations, animation);
+    animation.free();
+    fullRedraw = true;
+    const url = URL.createObjectURL(new Blob([bytes], {type: "image/gif"}));
+    const link = document.createElement("a");
+    link.href = url;
+    link.download = `generations-${start}-${grid.generation()}.gif`;
+    link.click();
+    URL.revokeObjectURL(url);
+}
+
+async function loadImage(event) {
+    const file = event.target.files[0];
+    if (!file) {
+        return;
+    }
+    try {
+        grid.load_image(new Uint8Array(await file.arrayBuffer()), 0.5);
+    } catch (error) {
+        alert(error);
+        return;
+    }
+    fullRedraw = true;
+}
+
+function changeVariant(event) {
+    grid.set_variant(Number(event.target.value));
+    fullRedraw = true;
+}
+
 function randomize() {
     grid.randomize();
+    fullRedraw = true;
 }
 
 function randomizeCenter() {
     grid.randomize_center();
+    fullRedraw = true;
 }
 
 function reset() {
     grid.reset();
+    fullRedraw = true;
+}
+
+function leniaSoup() {
+    lenia.spawn_soup(0, 0, 64, BigInt(Date.now()));
+}
+
+function leniaReset() {
+    lenia.reset();
+    lenia.spawn_creature(LeniaCreature.Orbium, 40, 40);
 }
 
 const pauseBtn = document.getElementById("pause");
@@ -114,13 +264,12 @@ const getIndex = (row, column) => {
 };
 
 const drawCells = () => {
-    const cellsPtr = grid.get_cells();
-    const cells = new Uint8Array(memory.buffer, cellsPtr, width * height);
+    const colours = grid.get_colours();
+    const cells = grid.get_cells();
 
     ctx.beginPath();
 
     // Alive cells.
-    ctx.fillStyle = ALIVE_COLOR;
     for (let row = 0; row < height; row++) {
         for (let col = 0; col < width; col++) {
             const idx = getIndex(row, col);
@@ -128,6 +277,7 @@ const drawCells = () => {
                 continue;
             }
 
+            ctx.fillStyle = palette[colours[idx] % palette.length];
             ctx.fillRect(
                 col * (CELL_SIZE + 1) + 1,
                 row * (CELL_SIZE + 1) + 1,
@@ -158,6 +308,50 @@ const drawCells = () => {
     ctx.stroke();
 };
 
+const drawChanges = () => {
+    const born = new Uint32Array(memory.buffer, grid.born(), grid.born_count());
+    born.forEach(idx => {
+        const [row, col] = [Math.floor(idx / width), idx % width];
+        ctx.fillStyle = palette[(grid.get_state(col, row) - 1) % palette.length];
+        fillCell(row, col);
+    });
+
+    const died = new Uint32Array(memory.buffer, grid.died(), grid.died_count());
+    ctx.fillStyle = DEAD_COLOR;
+    died.forEach(idx => fillCell(Math.floor(idx / width), idx % width));
+};
+
+const fillCell = (row, col) => {
+    ctx.fillRect(
+        col * (CELL_SIZE + 1) + 1,
+        row * (CELL_SIZE + 1) + 1,
+        CELL_SIZE,
+        CELL_SIZE
+    );
+};
+
+const drawLenia = () => {
+    const cellsPtr = lenia.get_cells();
+    const cells = new Float32Array(memory.buffer, cellsPtr, leniaWidth * leniaHeight);
+
+    for (let row = 0; row < leniaHeight; row++) {
+        for (let col = 0; col < leniaWidth; col++) {
+            const value = cells[getLeniaIndex(row, col)];
+            leniaCtx.fillStyle = `rgb(0, ${Math.floor(value * 255)}, ${Math.floor(value * 60)})`;
+            leniaCtx.fillRect(
+                col * LENIA_CELL_SIZE,
+                row * LENIA_CELL_SIZE,
+                LENIA_CELL_SIZE,
+                LENIA_CELL_SIZE
+            );
+        }
+    }
+};
+
+const getLeniaIndex = (row, column) => {
+    return row * leniaWidth + column;
+};
+
 
 canvas.addEventListener("click", event => {
     const boundingRect = canvas.getBoundingClientRect();
@@ -173,17 +367,75 @@ canvas.addEventListener("click", event => {
 
     console.log("click", x, y);
 
+    if (event.shiftKey) {
+        selectCorner(x, y);
+        return;
+    }
+    lastClick = [x, y];
+
     if (cursorSpawn.value === "9999") {
         grid.revive_cell(x, y);
     } else {
         const pattern = cursorSpawn.value;
-        grid.spawn_pattern(pattern, x, y);
+        grid.spawn_pattern(pattern, x, y, selectedColour());
     }
 
     drawGrid();
     drawCells();
 });
 
+// Shift-clicks alternate between the first and the opposite corner.
+let selectionStart = null;
+let lastClick = [0, 0];
+
+function selectCorner(x, y) {
+    if (!selectionStart) {
+        selectionStart = [x, y];
+        grid.clear_selection();
+    } else {
+        const [startX, startY] = selectionStart;
+        grid.select(Math.min(x, startX), Math.min(y, startY),
+            Math.abs(x - startX) + 1, Math.abs(y - startY) + 1);
+        selectionStart = null;
+    }
+    const selection = grid.selection();
+    document.getElementById("selection").textContent = selection.length
+        ? `Selected ${selection[2]}x${selection[3]} at (${selection[0]}, ${selection[1]})`
+        : `Corner at (${x}, ${y})`;
+}
+
+// Ctrl+C, Ctrl+X and Ctrl+V; pastes go to the last clicked cell.
+function clipboardKeys(event) {
+    if (!(event.ctrlKey || event.metaKey) || event.target.tagName === "TEXTAREA"
+        || event.target.tagName === "INPUT") {
+        return;
+    }
+    switch (event.key) {
+        case "c":
+            grid.copy_selection();
+            break;
+        case "x":
+            grid.cut_selection();
+            break;
+        case "v":
+            grid.paste(lastClick[0], lastClick[1],
+                Number(document.getElementById("pasteTransform").value),
+                Number(document.getElementById("pasteMode").value));
+            break;
+        default:
+            return;
+    }
+    event.preventDefault();
+    fullRedraw = true;
+}
+
+function clipboardRle() {
+    const rle = grid.clipboard_to_rle();
+    if (rle) {
+        document.getElementById("rle").value = rle;
+    }
+}
+
 function reviveCell() {
     const coordString = document.getElementById("coordsRevive").value;
     let coords = coordString.split("\n");
@@ -196,6 +448,7 @@ function reviveCell() {
             grid.revive_cell(x, y);
         }
     });
+    fullRedraw = true;
 }
 
 const setup = () => {
@@ -208,6 +461,12 @@ const setup = () => {
     const resetBtn = document.getElementById("reset");
     resetBtn.addEventListener("click", reset);
 
+    const leniaSoupBtn = document.getElementById("leniaSoup");
+    leniaSoupBtn.addEventListener("click", leniaSoup);
+
+    const leniaResetBtn = document.getElementById("leniaReset");
+    leniaResetBtn.addEventListener("click", leniaReset);
+
     const pauseBtn = document.getElementById("pause");
     pauseBtn.addEventListener("click", () => {
         if (paused) {
@@ -239,9 +498,31 @@ const setup = () => {
         cursorSpawn.appendChild(opt);
     });
 
+    const variantSelection = document.getElementById("variant");
+    variantSelection.addEventListener("change", changeVariant);
+
     const reviveBtn = document.getElementById("revive");
     reviveBtn.addEventListener("click", reviveCell);
 
+    const ruleFileBtn = document.getElementById("ruleFileBtn");
+    ruleFileBtn.addEventListener("click", loadRuleFile);
+
+    const savePngBtn = document.getElementById("savePng");
+    savePngBtn.addEventListener("click", savePng);
+
+    const shareLinkBtn = document.getElementById("shareLink");
+    shareLinkBtn.addEventListener("click", shareLink);
+
+    const recordGifBtn = document.getElementById("recordGif");
+    recordGifBtn.addEventListener("click", recordGif);
+
+    const clipboardRleBtn = document.getElementById("clipboardRle");
+    clipboardRleBtn.addEventListener("click", clipboardRle);
+    document.addEventListener("keydown", clipboardKeys);
+
+    const loadImageInput = document.getElementById("loadImage");
+    loadImageInput.addEventListener("change", loadImage);
+
     const rleBtn = document.getElementById("rleBtn");
     rleBtn.addEventListener("click", rleSpawn);
 
@@ -257,6 +538,11 @@ const setup = () => {
         delaySlider.value = delayMs;
     })
 
+    const fastForward = document.getElementById("fastForward");
+    fastForward.addEventListener("input", (event) => {
+        fastForwardMs = Number(event.target.value);
+    });
+
     const dimensions = document.getElementById("dimensions");
     dimensions.innerText = `${width} x ${height}`
 };